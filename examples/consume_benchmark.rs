@@ -0,0 +1,82 @@
+use consumable_vec::{ConsumableMut, ConsumableVec, IndexedConsumableVec};
+use std::time::{Duration, Instant};
+
+const ENTRIES: usize = 100_000;
+const ROUNDS: u32 = 20;
+
+/// Every thousandth line is a `RING`, every other tenth line a `+CREG` reply
+fn lines() -> Vec<String> {
+    (0..ENTRIES)
+        .map(|n| {
+            if n % 1000 == 0 {
+                "RING".to_string()
+            } else if n % 10 == 0 {
+                format!("+CREG: {}", n)
+            } else {
+                format!("+CMTI: \"SM\",{}", n)
+            }
+        })
+        .collect()
+}
+
+/// Consumption as done before: clone all matches, then remove them with `retain`
+fn clone_and_retain(data: &mut Vec<String>, pattern: &str) -> Option<Vec<String>> {
+    let consumed = data
+        .iter()
+        .filter(|d| d.trim().starts_with(pattern))
+        .map(|d| d.to_string())
+        .collect::<Vec<String>>();
+    data.retain(|d| !d.trim().starts_with(pattern));
+
+    if !consumed.is_empty() {
+        Some(consumed)
+    } else {
+        None
+    }
+}
+
+fn measure<D, S, F>(name: &str, setup: S, mut consume: F)
+where
+    S: Fn() -> D,
+    F: FnMut(&mut D, &str) -> usize,
+{
+    for (pattern, expected) in [("+CREG", 9_900), ("RING", 100)] {
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..ROUNDS {
+            let mut data = setup();
+            let start = Instant::now();
+            let consumed = consume(&mut data, pattern);
+            elapsed += start.elapsed();
+            assert_eq!(expected, consumed);
+        }
+        println!(
+            "{:<20} {:>5} matches: {:?} per consume",
+            name,
+            expected,
+            elapsed / ROUNDS
+        );
+    }
+}
+
+fn main() {
+    println!(
+        "Consuming from {} entries, build with --release for meaningful numbers",
+        ENTRIES
+    );
+
+    measure("clone and retain", lines, |data, pattern| {
+        clone_and_retain(data, pattern).unwrap().len()
+    });
+
+    measure(
+        "ConsumableVec",
+        || ConsumableVec::from(lines()),
+        |vec, pattern| vec.consume_mut(pattern.to_string()).unwrap().inner().len(),
+    );
+
+    measure(
+        "IndexedConsumableVec",
+        || lines().into_iter().collect::<IndexedConsumableVec>(),
+        |vec, pattern| vec.consume_mut(pattern.to_string()).unwrap().inner().len(),
+    );
+}
@@ -1,4 +1,4 @@
-use consumable_vec::{Consumable, SharedConsumableVec};
+use consumable_vec::SharedConsumableVec;
 use std::{thread, time::Duration};
 
 fn main() {
@@ -14,8 +14,9 @@ fn main() {
     });
 
     let consumer = thread::spawn(move || loop {
-        thread::sleep(Duration::from_millis(100));
-        if let Some(consumed) = con_vec.consume("Produced".to_string()) {
+        if let Some(consumed) =
+            con_vec.consume_blocking("Produced".to_string(), Duration::from_secs(1))
+        {
             println!("{:?}", consumed);
             if consumed.inner().iter().filter(|c| c.contains("99")).count() > 0 {
                 break;
@@ -0,0 +1,372 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Collecting AT command responses terminated by a final result code and
+//! separating unsolicited result codes
+
+use crate::{ConsumableVec, Shared, SharedConsumableVec, StringPattern, SubscriptionId};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::Duration;
+
+/// Final result code terminating the response to an AT command
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ResultCode {
+    /// `OK`
+    Ok,
+    /// `ERROR`
+    Error,
+    /// `+CME ERROR: <err>`, holding the numeric or verbose error
+    CmeError(String),
+    /// `+CMS ERROR: <err>`, holding the numeric or verbose error
+    CmsError(String),
+}
+
+impl ResultCode {
+    /// Parses a final result code, ignoring surrounding whitespace
+    ///
+    /// Returns `None` if `line` is no final result code.
+    pub fn parse(line: &str) -> Option<ResultCode> {
+        let line = line.trim();
+
+        match line {
+            "OK" => Some(ResultCode::Ok),
+            "ERROR" => Some(ResultCode::Error),
+            _ => {
+                if let Some(err) = line.strip_prefix("+CME ERROR:") {
+                    Some(ResultCode::CmeError(err.trim().to_string()))
+                } else {
+                    line.strip_prefix("+CMS ERROR:")
+                        .map(|err| ResultCode::CmsError(err.trim().to_string()))
+                }
+            }
+        }
+    }
+}
+
+/// Response to an AT command, consumed with `consume_response`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AtResponse {
+    /// Intermediate lines in the order they got added
+    pub lines: Vec<String>,
+    /// Final result code terminating the response
+    pub result: ResultCode,
+}
+
+impl AtResponse {
+    /// Returns `true` if the command succeeded
+    pub fn is_ok(&self) -> bool {
+        self.result == ResultCode::Ok
+    }
+}
+
+/// Registry of unsolicited result code (URC) prefixes of a `SharedConsumableVec<String>`
+///
+/// Lines starting with a registered prefix are handed to the sink of the registry
+/// as they are added, so they never get picked up by any consume call. Created
+/// by `SharedConsumableVec::urc_registry`.
+///
+/// Dropping the registry stops the diversion. The registry does not keep the
+/// vector alive.
+///
+/// Example:
+/// ```
+/// use consumable_vec::{Consumable, SharedConsumableVec};
+///
+/// let replies = SharedConsumableVec::default();
+/// let urcs = SharedConsumableVec::default();
+/// let urc_queue = urcs.clone();
+/// let registry = replies.urc_registry(move |urc| urc_queue.add(urc));
+/// registry.register("RING");
+/// registry.register("+CMTI:");
+///
+/// replies.add("RING".to_string());
+/// replies.add("+CSQ: 20,99".to_string());
+///
+/// assert!(replies.consume("RING".to_string()).is_none());
+/// assert!(urcs.consume("RING".to_string()).is_some());
+/// ```
+#[derive(Debug)]
+pub struct UrcRegistry {
+    shared: Weak<Shared<String>>,
+    prefixes: Arc<Mutex<Vec<String>>>,
+    id: SubscriptionId,
+}
+
+impl UrcRegistry {
+    /// Diverts lines starting with `prefix`, ignoring surrounding whitespace
+    pub fn register<S: Into<String>>(&self, prefix: S) {
+        let prefix = prefix.into().trim().to_string();
+        let mut prefixes = self.prefixes.lock().unwrap();
+        if !prefixes.contains(&prefix) {
+            prefixes.push(prefix);
+        }
+    }
+
+    /// Stops diverting lines starting with `prefix`, returns `false` if it was not registered
+    pub fn unregister(&self, prefix: &str) -> bool {
+        let mut prefixes = self.prefixes.lock().unwrap();
+        let count = prefixes.len();
+        prefixes.retain(|p| p != prefix.trim());
+        prefixes.len() != count
+    }
+
+    /// Returns `true` if `line` starts with a registered prefix
+    pub fn is_urc(&self, line: &str) -> bool {
+        is_urc(&self.prefixes, line)
+    }
+
+    /// Removes the registry, URCs added afterwards are stored as usual
+    ///
+    /// This is the same as dropping the registry.
+    pub fn close(self) {}
+}
+
+impl Drop for UrcRegistry {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.upgrade() {
+            // removing the subscription is fine even if another thread panicked
+            shared
+                .subscriptions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .unsubscribe(self.id);
+        }
+    }
+}
+
+fn is_urc(prefixes: &Mutex<Vec<String>>, line: &str) -> bool {
+    let line = line.trim();
+    prefixes
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|prefix| line.starts_with(prefix.as_str()))
+}
+
+impl ConsumableVec<String> {
+    /// # AT response consume method
+    /// Removes the oldest final result code together with all preceding lines
+    /// matched by `intermediate` and returns them as one response.
+    ///
+    /// Preceding lines not matched by `intermediate`, as well as all lines after
+    /// the final result code, stay in the vector. Returns `None` if no final
+    /// result code got added yet.
+    ///
+    /// Example:
+    /// ```
+    /// use consumable_vec::{ResultCode, SharedConsumableVec, StringPattern};
+    ///
+    /// let replies = SharedConsumableVec::default();
+    /// replies.add("+CSQ: 20,99".to_string());
+    /// replies.add("RING".to_string());
+    /// replies.add("OK".to_string());
+    ///
+    /// let response = replies.consume_response(&StringPattern::new("+CSQ:")).unwrap();
+    /// assert_eq!(vec!["+CSQ: 20,99".to_string()], response.lines);
+    /// assert_eq!(ResultCode::Ok, response.result);
+    /// ```
+    pub fn consume_response(&mut self, intermediate: &StringPattern) -> Option<AtResponse> {
+        self.remove_expired();
+
+        let (end, result) = self
+            .data
+            .iter()
+            .enumerate()
+            .find_map(|(index, line)| ResultCode::parse(line).map(|result| (index, result)))?;
+
+        let mut index = 0;
+        // no expiry in between, `end` has to stay the index of the result code
+        let mut lines = self
+            .extract_where(|line| {
+                let related = index == end || (index < end && intermediate.matches(line));
+                index += 1;
+                related
+            })?
+            .data;
+        // the final result code is always the last consumed line
+        lines.pop();
+
+        Some(AtResponse { lines, result })
+    }
+}
+
+impl SharedConsumableVec<String> {
+    /// # AT response consume method
+    /// Removes the oldest final result code together with all preceding lines
+    /// matched by `intermediate`, see `ConsumableVec::consume_response`
+    pub fn consume_response(&self, intermediate: &StringPattern) -> Option<AtResponse> {
+        self.consume_with(|data| data.consume_response(intermediate))
+    }
+
+    /// # Blocking AT response consume method
+    /// Waits until a final result code got added and consumes the response, see
+    /// `consume_response`. Returns `None` if no final result code got added
+    /// within `timeout`.
+    pub fn consume_response_blocking(
+        &self,
+        intermediate: &StringPattern,
+        timeout: Duration,
+    ) -> Option<AtResponse> {
+        self.wait_for(timeout, |data| data.consume_response(intermediate))
+    }
+
+    /// Creates a registry of unsolicited result code prefixes, diverting all
+    /// matching lines added afterwards to `sink`, see `UrcRegistry`
+    ///
+    /// The registry is a subscription, create it before other subscriptions
+    /// so it receives URCs matched by those as well.
+    pub fn urc_registry<F>(&self, sink: F) -> UrcRegistry
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        let prefixes = Arc::new(Mutex::new(Vec::new()));
+        let registered = Arc::clone(&prefixes);
+        let id = self.subscribe_where(move |line: &String| is_urc(&registered, line), sink);
+
+        UrcRegistry {
+            shared: Arc::downgrade(&self.shared),
+            prefixes,
+            id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_at_responses {
+    use super::*;
+    use len_trait::Len;
+
+    #[test]
+    fn parse_should_recognize_final_result_codes() {
+        assert_eq!(Some(ResultCode::Ok), ResultCode::parse("OK\r\n"));
+        assert_eq!(Some(ResultCode::Error), ResultCode::parse(" ERROR"));
+        assert_eq!(
+            Some(ResultCode::CmeError("10".to_string())),
+            ResultCode::parse("+CME ERROR: 10")
+        );
+        assert_eq!(
+            Some(ResultCode::CmsError("500".to_string())),
+            ResultCode::parse("+CMS ERROR:500")
+        );
+        assert_eq!(None, ResultCode::parse("OKAY"));
+    }
+
+    #[test]
+    fn consume_response_when_no_final_result_code_should_return_none() {
+        let mut at = ConsumableVec::default();
+        at.add("+CSQ: 20,99".to_string());
+        assert!(at.consume_response(&StringPattern::new("+CSQ")).is_none());
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn consume_response_should_leave_unrelated_and_later_lines() {
+        let mut at = ConsumableVec::default();
+        at.add("+COPS: 0,0,\"Operator\"".to_string());
+        at.add("+CREG: 1".to_string());
+        at.add("+CME ERROR: 30".to_string());
+        at.add("+COPS: 1".to_string());
+        at.add("OK".to_string());
+        let response = at.consume_response(&StringPattern::new("+COPS")).unwrap();
+        assert_eq!(vec!["+COPS: 0,0,\"Operator\"".to_string()], response.lines);
+        assert_eq!(ResultCode::CmeError("30".to_string()), response.result);
+        assert!(!response.is_ok());
+        assert_eq!(
+            vec![
+                "+CREG: 1".to_string(),
+                "+COPS: 1".to_string(),
+                "OK".to_string()
+            ],
+            at.data
+        );
+    }
+
+    #[test]
+    fn consume_response_should_never_contain_urcs() {
+        let at = SharedConsumableVec::default();
+        let urcs = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&urcs);
+        let registry = at.urc_registry(move |urc| sink.lock().unwrap().push(urc));
+        registry.register(" +CREG:");
+        registry.register("RING");
+
+        at.add("+CREG: 1".to_string());
+        at.add("RING".to_string());
+        at.add("OK".to_string());
+
+        let response = at.consume_response(&StringPattern::new("")).unwrap();
+        assert!(response.lines.is_empty());
+        assert_eq!(
+            vec!["+CREG: 1".to_string(), "RING".to_string()],
+            *urcs.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn urc_registry_when_unregistered_or_closed_should_store_lines() {
+        let at = SharedConsumableVec::default();
+        let registry = at.urc_registry(|_| {});
+        registry.register("RING");
+        registry.register("+CMTI:");
+        assert!(registry.unregister("RING"));
+        assert!(!registry.unregister("RING"));
+        at.add("RING".to_string());
+        at.add("+CMTI: \"SM\",1".to_string());
+        assert_eq!(1, at.len());
+
+        registry.close();
+        at.add("+CMTI: \"SM\",2".to_string());
+        assert_eq!(2, at.len());
+    }
+
+    #[test]
+    fn urc_registry_when_dropped_should_store_lines() {
+        let at = SharedConsumableVec::default();
+        let registry = at.urc_registry(|_| {});
+        registry.register("RING");
+        at.add("RING".to_string());
+        assert_eq!(0, at.len());
+
+        drop(registry);
+        at.add("RING".to_string());
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn urc_registry_should_not_keep_vector_alive() {
+        let at = SharedConsumableVec::<String>::default();
+        let shared = Arc::downgrade(&at.shared);
+        let registry = at.urc_registry(|_| {});
+
+        drop(at);
+        assert!(shared.upgrade().is_none());
+        registry.register("RING");
+    }
+
+    #[test]
+    fn consume_response_blocking_should_wait_for_final_result_code() {
+        let at = SharedConsumableVec::default();
+        let modem = at.clone();
+
+        let handle = std::thread::spawn(move || {
+            modem.add("Manufacturer".to_string());
+            std::thread::sleep(Duration::from_millis(20));
+            modem.add("OK".to_string());
+        });
+
+        let response = at
+            .consume_response_blocking(&StringPattern::new(""), Duration::from_secs(10))
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!(vec!["Manufacturer".to_string()], response.lines);
+        assert!(response.is_ok());
+        assert_eq!(0, at.len());
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Errors of fallible operations on a `SharedConsumableVec`
+
+use std::error::Error;
+use std::fmt;
+
+/// Error returned by the `try_` methods of `SharedConsumableVec`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsumeError {
+    /// Another thread panicked while holding the lock of the shared data
+    Poisoned,
+}
+
+impl fmt::Display for ConsumeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsumeError::Poisoned => write!(f, "shared data is poisoned by a panicked thread"),
+        }
+    }
+}
+
+impl Error for ConsumeError {}
+
+/// How a `SharedConsumableVec` reacts to a lock poisoned by a panicked thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoisonPolicy {
+    /// Fail with `ConsumeError::Poisoned`, the non `try_` methods panic
+    #[default]
+    Fail,
+    /// Keep using the inner data as it was left by the panicked thread
+    Recover,
+}
+
+/// Error returned by `SharedConsumableVec::try_add`, handing back the rejected item
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TryAddError<T> {
+    /// The capacity limit of the vector is reached
+    Full(T),
+    /// Another thread panicked while holding the lock of the shared data
+    Poisoned(T),
+}
+
+impl<T> TryAddError<T> {
+    /// Returns the item which could not be added
+    pub fn into_inner(self) -> T {
+        match self {
+            TryAddError::Full(item) | TryAddError::Poisoned(item) => item,
+        }
+    }
+}
+
+// not derived, printing the error must not require `T: Debug`
+impl<T> fmt::Debug for TryAddError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryAddError::Full(_) => write!(f, "Full(..)"),
+            TryAddError::Poisoned(_) => write!(f, "Poisoned(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for TryAddError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryAddError::Full(_) => write!(f, "capacity limit of shared data is reached"),
+            TryAddError::Poisoned(_) => write!(f, "{}", ConsumeError::Poisoned),
+        }
+    }
+}
+
+impl<T> Error for TryAddError<T> {}
@@ -0,0 +1,123 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Futures resolving once data could be consumed from a `SharedConsumableVec`
+
+use crate::{ConsumableVec, SharedConsumableVec};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future repeatedly applying `consume` to the inner data of a `SharedConsumableVec`
+///
+/// Whenever `consume` returns `None` the waker of the current task is registered
+/// and woken again by the next `add`.
+pub(crate) struct WaitFor<T, F> {
+    vec: SharedConsumableVec<T>,
+    consume: F,
+}
+
+impl<T, F> WaitFor<T, F> {
+    pub(crate) fn new(vec: SharedConsumableVec<T>, consume: F) -> Self {
+        WaitFor { vec, consume }
+    }
+}
+
+impl<T, R, F> Future for WaitFor<T, F>
+where
+    F: FnMut(&mut ConsumableVec<T>) -> Option<R> + Unpin,
+{
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let this = self.get_mut();
+        let mut data = this.vec.lock().unwrap();
+
+        let len = data.data.len();
+        if let Some(consumed) = (this.consume)(&mut data) {
+            this.vec
+                .shared
+                .counters
+                .record_consume(len - data.data.len());
+            drop(data);
+            this.vec.notify_removed();
+            return Poll::Ready(consumed);
+        }
+
+        // registering while the data is still locked guarantees that no `add`
+        // can slip in between the failed attempt and the registration
+        let mut wakers = this.vec.shared.wakers.lock().unwrap();
+        if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test_shared_async_replies {
+    use crate::SharedConsumableVec;
+    use len_trait::Len;
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+    use std::time::Duration;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn consume_async_when_pattern_in_replies_should_be_ready() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        let consumed = block_on(at.consume_async("da".to_string()));
+        assert_eq!(1, consumed.len());
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn consume_async_should_wake_up_when_pattern_gets_added() {
+        let at = SharedConsumableVec::default();
+        let producer = at.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.add("ata".to_string());
+            thread::sleep(Duration::from_millis(20));
+            producer.add("data".to_string());
+        });
+
+        let consumed = block_on(at.consume_async("da".to_string()));
+        handle.join().unwrap();
+        assert_eq!("data".to_string(), consumed.data[0]);
+        assert_eq!(1, at.len());
+    }
+}
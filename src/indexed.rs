@@ -0,0 +1,251 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Prefix indexed vector of `String`s
+
+use crate::{ConsumableMut, ConsumableVec};
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+use std::ops::Bound;
+
+/// Vector of `String`s indexed by their trimmed content
+///
+/// Consumes the same entries as `ConsumableVec<String>::consume_mut`, but
+/// looks them up in an ordered index instead of scanning all entries.
+/// Consuming costs time logarithmic in the number of entries plus a share
+/// per match, which is more per match than the plain scan. So this only pays
+/// off if a pattern matches few of many entries, e.g. picking single replies
+/// out of a long log. If patterns regularly match a large part of the
+/// entries, `ConsumableVec` is faster, see the `consume_benchmark` example.
+///
+/// Example:
+/// ```
+/// use consumable_vec::{ConsumableMut, IndexedConsumableVec};
+///
+/// let mut at = IndexedConsumableVec::new();
+/// at.add("+CREG: 1".to_string());
+/// at.add("+CMTI: \"SM\",3".to_string());
+/// at.add("  +CREG: 2".to_string());
+///
+/// let creg = at.consume_mut("+CREG".to_string()).unwrap();
+/// assert_eq!(&vec!["+CREG: 1", "  +CREG: 2"], creg.inner());
+/// assert_eq!(1, at.iter().count());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IndexedConsumableVec {
+    /// entries by their insertion number, iterating in insertion order
+    entries: BTreeMap<u64, String>,
+    /// insertion numbers of the entries by their trimmed content, ascending
+    index: BTreeMap<String, Vec<u64>>,
+    next_id: u64,
+}
+
+impl IndexedConsumableVec {
+    pub fn new() -> Self {
+        IndexedConsumableVec::default()
+    }
+
+    pub fn add(&mut self, reply: String) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.index
+            .entry(reply.trim().to_string())
+            .or_default()
+            .push(id);
+        self.entries.insert(id, reply);
+        event!(len = self.entries.len(), "added");
+    }
+
+    pub fn clear(&mut self) {
+        event!(removed = self.entries.len(), "cleared");
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    /// Iterates all entries in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.entries.values()
+    }
+
+    /// # Single consume method
+    /// Removes and returns the oldest entry `consume_mut` would consume for `pattern`
+    pub fn consume_first(&mut self, pattern: String) -> Option<String> {
+        let (key, id) = self
+            .matching(pattern.trim())
+            .map(|(key, ids)| (key, ids[0]))
+            .min_by_key(|(_, id)| *id)?;
+
+        let key = key.clone();
+        let ids = self.index.get_mut(&key)?;
+        ids.remove(0);
+        if ids.is_empty() {
+            self.index.remove(&key);
+        }
+        self.entries.remove(&id)
+    }
+
+    /// Returns `true` if `consume_mut` would consume anything for `pattern`
+    pub fn contains(&self, pattern: String) -> bool {
+        self.matching(pattern.trim()).next().is_some()
+    }
+
+    /// Iterates the index entries of all keys starting with `prefix`
+    fn matching<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a String, &'a Vec<u64>)> {
+        self.index
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(prefix))
+    }
+}
+
+impl ConsumableMut for IndexedConsumableVec {
+    type Item = ConsumableVec<String>;
+    type DataType = String;
+
+    /// Consumes all entries starting with `pattern`, both trimmed, in insertion order
+    fn consume_mut(&mut self, pattern: Self::DataType) -> Option<Self::Item> {
+        span!("consume_mut", pattern = %pattern);
+
+        let keys: Vec<String> = self
+            .matching(pattern.trim())
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut ids: Vec<u64> = keys
+            .iter()
+            .filter_map(|key| self.index.remove(key))
+            .flatten()
+            .collect();
+        ids.sort_unstable();
+
+        let consumed: Vec<String> = ids
+            .iter()
+            .filter_map(|id| self.entries.remove(id))
+            .collect();
+        event!(
+            matched = consumed.len(),
+            remaining = self.entries.len(),
+            "consumed"
+        );
+
+        if !consumed.is_empty() {
+            Some(ConsumableVec::new(Some(consumed)))
+        } else {
+            None
+        }
+    }
+}
+
+impl len_trait::Len for IndexedConsumableVec {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl len_trait::Empty for IndexedConsumableVec {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl FromIterator<String> for IndexedConsumableVec {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        let mut vec = IndexedConsumableVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl Extend<String> for IndexedConsumableVec {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        for reply in iter {
+            self.add(reply);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_indexed_replies {
+    use super::*;
+    use len_trait::{Empty, Len};
+
+    fn replies() -> IndexedConsumableVec {
+        vec!["+CREG: 2", "+CMTI: 3", " +CREG: 1 ", "+CR", "OK"]
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn consume_mut_should_return_prefix_matches_in_insertion_order() {
+        let mut at = replies();
+        let consumed = at.consume_mut("+C".to_string()).unwrap();
+        assert_eq!(
+            &vec!["+CREG: 2", "+CMTI: 3", " +CREG: 1 ", "+CR"],
+            consumed.inner()
+        );
+        assert_eq!(vec!["OK"], at.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn consume_mut_should_match_like_consumable_vec() {
+        for pattern in ["+CREG", " +CR ", "+CREG: 1", "", "ERROR"] {
+            let mut indexed = replies();
+            let mut plain: ConsumableVec<String> = replies().iter().cloned().collect();
+
+            assert_eq!(
+                plain.consume_mut(pattern.to_string()),
+                indexed.consume_mut(pattern.to_string())
+            );
+            assert_eq!(
+                plain.iter().collect::<Vec<_>>(),
+                indexed.iter().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn consume_mut_when_pattern_not_in_replies_should_return_none() {
+        let mut at = replies();
+        assert!(at.consume_mut("+CREGX".to_string()).is_none());
+        assert_eq!(5, at.len());
+    }
+
+    #[test]
+    fn consume_first_should_remove_oldest_match_only() {
+        let mut at = replies();
+        assert_eq!("+CREG: 2", at.consume_first("+CREG".to_string()).unwrap());
+        assert_eq!(" +CREG: 1 ", at.consume_first("+CREG".to_string()).unwrap());
+        assert!(at.consume_first("+CREG".to_string()).is_none());
+        assert!(at.contains("+CR".to_string()));
+        assert_eq!(3, at.len());
+    }
+
+    #[test]
+    fn contains_should_not_consume() {
+        let mut at = replies();
+        assert!(at.contains("+CM".to_string()));
+        assert!(!at.contains("ERROR".to_string()));
+        at.clear();
+        assert!(!at.contains("+CM".to_string()));
+        assert!(at.is_empty());
+    }
+
+    #[test]
+    fn long_line_should_be_added_and_consumed() {
+        let line = "+CMGR: ".to_string() + &"A".repeat(200_000);
+        let mut at = IndexedConsumableVec::new();
+        at.add("OK".to_string());
+        at.add(line.clone());
+        assert!(at.contains("+CMGR".to_string()));
+
+        let consumed = at.consume_mut(line[..100_000].to_string()).unwrap();
+        assert_eq!(&vec![line], consumed.inner());
+        assert_eq!(1, at.len());
+    }
+}
@@ -0,0 +1,531 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Persistent shared vector backed by a write-ahead journal, enabled by the
+//! `journal` feature
+
+use crate::{ConsumableVec, SharedConsumableVec, StringPattern};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Number of journal records after which the journal gets compacted by default
+const DEFAULT_COMPACT_AFTER: usize = 1000;
+
+/// Single line of the journal
+#[derive(Serialize, Deserialize)]
+enum Record<T> {
+    Add { id: u64, item: T },
+    Consume { ids: Vec<u64> },
+    Clear,
+}
+
+#[derive(Debug)]
+struct Journal {
+    path: PathBuf,
+    file: File,
+    /// length of the journal up to the end of the last complete record
+    len: u64,
+    /// set if a partially written record could not be removed again, or if
+    /// the rename of a compacted journal could not be synced
+    torn: bool,
+    next_id: u64,
+    /// records written since the last compaction
+    records: usize,
+    compact_after: usize,
+}
+
+impl Journal {
+    /// Appends `record` and syncs it to disk
+    ///
+    /// If this fails, the journal is truncated to its previous length, as a
+    /// partially written record would hide all records following it.
+    fn write<T: Serialize>(&mut self, record: &Record<T>) -> io::Result<()> {
+        if self.torn {
+            return Err(io::Error::other(
+                "journal is in an inconsistent state, compact it first",
+            ));
+        }
+
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        if let Err(e) = self
+            .file
+            .write_all(&line)
+            .and_then(|_| self.file.sync_data())
+        {
+            self.torn = self.file.set_len(self.len).is_err();
+            return Err(e);
+        }
+
+        self.len += line.len() as u64;
+        self.records += 1;
+        Ok(())
+    }
+
+    /// Replaces the journal by one only adding `entries`
+    ///
+    /// If the replacement could not be synced, the journal is marked as torn,
+    /// as records appended to it might get lost with the rename.
+    fn compact<T: Serialize>(&mut self, entries: &[(u64, T)]) -> io::Result<()> {
+        let (file, len) = rewrite(&self.path, entries)?;
+        self.file = file;
+        self.len = len;
+        self.records = 0;
+        if let Err(e) = sync_dir(&self.path) {
+            self.torn = true;
+            return Err(e);
+        }
+        self.torn = false;
+        Ok(())
+    }
+}
+
+/// Atomically replaces the journal at `path` by one only adding `entries` and
+/// returns it opened for appending, together with its length
+///
+/// The replacement is opened before the rename, so the returned handle refers
+/// to the journal at `path` as soon as the rename succeeded. The rename is not
+/// synced yet, see `sync_dir`.
+fn rewrite<T: Serialize>(path: &Path, entries: &[(u64, T)]) -> io::Result<(File, u64)> {
+    let compacted = path.with_extension("compact");
+    write_entries(&compacted, entries)?;
+    let file = OpenOptions::new().append(true).open(&compacted)?;
+    let len = file.metadata()?.len();
+    fs::rename(&compacted, path)?;
+
+    Ok((file, len))
+}
+
+fn write_entries<T: Serialize>(path: &Path, entries: &[(u64, T)]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for (id, item) in entries {
+        serde_json::to_writer(&mut file, &Record::Add { id: *id, item })?;
+        file.write_all(b"\n")?;
+    }
+    file.flush()?;
+    file.get_ref().sync_all()
+}
+
+/// Syncs the directory containing `path`, making a rename within it durable
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Rebuilds the entries from the journal at `path`, in insertion order
+///
+/// An incomplete or corrupt last record, e.g. one torn by a crash while
+/// writing it, is ignored. Any other corrupt record fails with
+/// `io::ErrorKind::InvalidData`, instead of dropping all records after it.
+fn replay<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<(u64, T)>> {
+    let mut entries = BTreeMap::new();
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut torn = None;
+    for line in BufReader::new(file).split(b'\n') {
+        let line = line?;
+        if let Some(e) = torn.take() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+
+        match serde_json::from_slice(&line) {
+            Ok(Record::Add { id, item }) => {
+                entries.insert(id, item);
+            }
+            Ok(Record::Consume { ids }) => {
+                for id in ids {
+                    entries.remove(&id);
+                }
+            }
+            Ok(Record::Clear) => entries.clear(),
+            Err(e) => torn = Some(e),
+        }
+    }
+
+    Ok(entries.into_iter().collect())
+}
+
+/// Shared consumable vector persisting its content in a journal file
+///
+/// Every `add` and every consumption is appended to the journal and synced to
+/// disk before it takes effect, so the content can be rebuilt by `open` after
+/// a process crash or a power loss, as far as the storage honors syncing.
+/// The sync makes every call as slow as a disk write. Consumed entries are
+/// journaled before they are handed out, so entries consumed right before a
+/// crash are not restored, even if the consumer could not process them.
+/// After a configurable number of records the journal is compacted to hold
+/// only the current entries, right before the next change gets journaled.
+///
+/// In contrast to `SharedConsumableVec`, every method returns an `io::Error`
+/// if the journal could not be written or compacted. The change does not take
+/// effect then, e.g. entries to be consumed stay in the vector.
+///
+/// Example:
+/// ```
+/// use consumable_vec::JournaledConsumableVec;
+///
+/// let path = std::env::temp_dir().join("consumable_vec_doc.journal");
+/// # let _ = std::fs::remove_file(&path);
+/// let vec = JournaledConsumableVec::open(&path).unwrap();
+/// vec.add("Produced: 1".to_string()).unwrap();
+/// vec.add("Produced: 2".to_string()).unwrap();
+/// vec.consume_first("Produced".to_string()).unwrap();
+/// drop(vec);
+///
+/// let restored = JournaledConsumableVec::<String>::open(&path).unwrap();
+/// let consumed = restored.consume("Produced".to_string()).unwrap().unwrap();
+/// assert_eq!(vec!["Produced: 2".to_string()], consumed.inner().clone());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct JournaledConsumableVec<T> {
+    vec: SharedConsumableVec<(u64, T)>,
+    journal: Arc<Mutex<Journal>>,
+}
+
+impl<T> JournaledConsumableVec<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Opens the journal at `path`, rebuilding the entries it contains
+    ///
+    /// The journal file is created if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = replay::<T>(&path)?;
+        let next_id = entries.last().map_or(0, |(id, _)| id + 1);
+
+        // start with a compacted journal, dropping a torn record at its end
+        let (file, len) = rewrite(&path, &entries)?;
+        sync_dir(&path)?;
+
+        Ok(JournaledConsumableVec {
+            vec: SharedConsumableVec::new(Some(entries)),
+            journal: Arc::new(Mutex::new(Journal {
+                path,
+                file,
+                len,
+                torn: false,
+                next_id,
+                records: 0,
+                compact_after: DEFAULT_COMPACT_AFTER,
+            })),
+        })
+    }
+
+    /// Sets the number of journal records after which the journal gets compacted
+    pub fn set_compact_after(&self, records: usize) {
+        self.journal.lock().unwrap().compact_after = records;
+    }
+
+    pub fn add(&self, reply: T) -> io::Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        self.compact_if_due(&mut journal)?;
+        let id = journal.next_id;
+        journal.write(&Record::Add { id, item: &reply })?;
+        journal.next_id += 1;
+
+        self.vec.add((id, reply));
+        Ok(())
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        self.compact_if_due(&mut journal)?;
+        journal.write(&Record::<T>::Clear)?;
+
+        self.vec.clear();
+        Ok(())
+    }
+
+    /// # Predicate based consume method
+    /// Removes all entries for which `predicate` returns `true`, see
+    /// `ConsumableVec::consume_where`
+    pub fn consume_where<F>(&self, mut predicate: F) -> io::Result<Option<ConsumableVec<T>>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.consume_entries(|data| {
+            data.iter()
+                .filter(|(_, item)| predicate(item))
+                .map(|(id, _)| *id)
+                .collect()
+        })
+    }
+
+    /// # Single predicate based consume method
+    /// Removes the oldest matching entry, see `ConsumableVec::consume_first_where`
+    pub fn consume_first_where<F>(&self, mut predicate: F) -> io::Result<Option<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let consumed = self.consume_entries(|data| {
+            data.iter()
+                .find(|(_, item)| predicate(item))
+                .map(|(id, _)| *id)
+                .into_iter()
+                .collect()
+        })?;
+        Ok(consumed.and_then(|c| c.into_iter().next()))
+    }
+
+    /// Forces a compaction of the journal
+    pub fn compact(&self) -> io::Result<()> {
+        let mut journal = self.journal.lock().unwrap();
+        self.compact_locked(&mut journal)
+    }
+
+    /// Journals the entries whose ids `select` returns, then removes them
+    ///
+    /// The journal and the entries stay locked meanwhile, so the order of
+    /// records matches the order of changes. `select` must return the ids in
+    /// insertion order.
+    fn consume_entries<F>(&self, select: F) -> io::Result<Option<ConsumableVec<T>>>
+    where
+        F: FnOnce(&ConsumableVec<(u64, T)>) -> Vec<u64>,
+    {
+        let mut journal = self.journal.lock().unwrap();
+        self.compact_if_due(&mut journal)?;
+        let consumed = self.vec.consume_with(|data| -> io::Result<_> {
+            let ids = select(data);
+            if ids.is_empty() {
+                return Ok(None);
+            }
+
+            journal.write(&Record::<T>::Consume { ids: ids.clone() })?;
+            Ok(data.consume_where(|(id, _)| ids.binary_search(id).is_ok()))
+        })?;
+
+        Ok(consumed.map(|consumed| consumed.into_iter().map(|(_, item)| item).collect()))
+    }
+
+    /// Compacts the journal if enough records got written since the last compaction
+    ///
+    /// Called before journaling a change rather than after it, so a failing
+    /// compaction fails the call before the change takes effect.
+    fn compact_if_due(&self, journal: &mut Journal) -> io::Result<()> {
+        if journal.records >= journal.compact_after {
+            self.compact_locked(journal)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn compact_locked(&self, journal: &mut Journal) -> io::Result<()> {
+        let data = self.vec.lock().unwrap();
+        journal.compact(data.inner())
+    }
+}
+
+impl JournaledConsumableVec<String> {
+    /// # Consume method
+    /// Removes all entries matching `pattern` with the same trimmed prefix
+    /// matching as `SharedConsumableVec::consume`
+    pub fn consume(&self, pattern: String) -> io::Result<Option<ConsumableVec<String>>> {
+        let pattern = StringPattern::new(pattern);
+        self.consume_where(|d| pattern.matches(d))
+    }
+
+    /// # Single consume method
+    /// Removes the oldest entry matching `pattern`
+    pub fn consume_first(&self, pattern: String) -> io::Result<Option<String>> {
+        let pattern = StringPattern::new(pattern);
+        self.consume_first_where(|d| pattern.matches(d))
+    }
+}
+
+// not derived, sharing the data must not require `T: Clone`
+impl<T> Clone for JournaledConsumableVec<T> {
+    fn clone(&self) -> Self {
+        JournaledConsumableVec {
+            vec: self.vec.clone(),
+            journal: Arc::clone(&self.journal),
+        }
+    }
+}
+
+impl<T> len_trait::Len for JournaledConsumableVec<T> {
+    fn len(&self) -> usize {
+        self.vec.len()
+    }
+}
+
+impl<T> len_trait::Empty for JournaledConsumableVec<T> {
+    fn is_empty(&self) -> bool {
+        self.vec.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test_journaled_replies {
+    use super::*;
+    use len_trait::Len;
+
+    fn journal_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "consumable_vec_{}_{}.journal",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn open_should_restore_unconsumed_values() {
+        let path = journal_path("restore");
+        let at = JournaledConsumableVec::open(&path).unwrap();
+        at.add("data".to_string()).unwrap();
+        at.add("ata".to_string()).unwrap();
+        at.add("data2".to_string()).unwrap();
+        assert_eq!(2, at.consume("da".to_string()).unwrap().unwrap().len());
+        at.add("data3".to_string()).unwrap();
+        drop(at);
+
+        let at = JournaledConsumableVec::<String>::open(&path).unwrap();
+        assert_eq!(2, at.len());
+        assert_eq!(
+            Some("ata".to_string()),
+            at.consume_first("at".to_string()).unwrap()
+        );
+        assert_eq!(
+            Some("data3".to_string()),
+            at.consume_first("da".to_string()).unwrap()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_should_ignore_torn_record_at_end() {
+        let path = journal_path("torn");
+        let at = JournaledConsumableVec::open(&path).unwrap();
+        at.add("data".to_string()).unwrap();
+        at.clear().unwrap();
+        at.add("ata".to_string()).unwrap();
+        drop(at);
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"Add\":{\"id\":7,\"it")
+            .unwrap();
+
+        let at = JournaledConsumableVec::<String>::open(&path).unwrap();
+        at.add("data2".to_string()).unwrap();
+        drop(at);
+
+        let at = JournaledConsumableVec::<String>::open(&path).unwrap();
+        let consumed = at.consume_where(|_| true).unwrap().unwrap();
+        assert_eq!(vec!["ata".to_string(), "data2".to_string()], consumed.data);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn open_when_record_before_last_corrupt_should_fail() {
+        let path = journal_path("corrupt");
+        let at = JournaledConsumableVec::open(&path).unwrap();
+        at.add("data".to_string()).unwrap();
+        drop(at);
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"Add\":{\"id\":1,\"it\n").unwrap();
+        file.write_all(b"{\"Add\":{\"id\":2,\"item\":\"ata\"}}\n")
+            .unwrap();
+
+        let err = JournaledConsumableVec::<String>::open(&path).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn consume_when_journal_write_fails_should_keep_values() {
+        let path = journal_path("write_fails");
+        let at = JournaledConsumableVec::open(&path).unwrap();
+        at.add("data".to_string()).unwrap();
+        at.add("ata".to_string()).unwrap();
+        at.journal.lock().unwrap().file = OpenOptions::new().write(true).open("/dev/full").unwrap();
+
+        assert!(at.consume("da".to_string()).is_err());
+        assert!(at.consume_first("at".to_string()).is_err());
+        assert_eq!(2, at.len());
+
+        // the failed record could not be truncated from /dev/full, so writes
+        // fail until the journal is rewritten
+        at.compact().unwrap();
+        assert_eq!(
+            Some("data".to_string()),
+            at.consume_first("da".to_string()).unwrap()
+        );
+        drop(at);
+
+        let at = JournaledConsumableVec::<String>::open(&path).unwrap();
+        assert_eq!(1, at.len());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn add_when_compaction_fails_should_not_add_value() {
+        let path = journal_path("compact_fails");
+        let at = JournaledConsumableVec::open(&path).unwrap();
+        at.set_compact_after(1);
+        at.add("data".to_string()).unwrap();
+        // the compacted journal can not be created in place of a directory
+        fs::create_dir(path.with_extension("compact")).unwrap();
+
+        assert!(at.add("ata".to_string()).is_err());
+        assert_eq!(1, at.len());
+
+        fs::remove_dir(path.with_extension("compact")).unwrap();
+        at.add("ata".to_string()).unwrap();
+        drop(at);
+
+        let at = JournaledConsumableVec::<String>::open(&path).unwrap();
+        assert_eq!(2, at.len());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn compaction_should_shrink_journal_to_current_values() {
+        let path = journal_path("compact");
+        let at = JournaledConsumableVec::open(&path).unwrap();
+        at.set_compact_after(4);
+        for n in 0..3 {
+            at.add(format!("data{}", n)).unwrap();
+        }
+        at.consume("data".to_string()).unwrap();
+        at.add("ata".to_string()).unwrap();
+
+        let journal = fs::read_to_string(&path).unwrap();
+        assert_eq!(1, journal.lines().count());
+        drop(at);
+
+        let at = JournaledConsumableVec::<String>::open(&path).unwrap();
+        assert_eq!(1, at.len());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -15,11 +15,11 @@
 //! pattern is fulfilled in a `String` implementation.
 //!
 //! This crate provides two different implementations:
-//! The struct `ConsumableVec` is a plain implementation of the trait `Consumable`. Here the
+//! The struct `ConsumableVec` is a plain implementation of the trait `ConsumableMut`. Here the
 //! user needs to take care of the ownership of the object when adding data or trying to consume
 //! data from it.    
 //! The struct `SharedConsumableVec` uses a `ConsumableVec` which can be referenced by multiple owners
-//! from multiple threads.
+//! from multiple threads and implements the trait `Consumable`.
 //! ## Example:
 //! ```
 //! use consumable_vec::{SharedConsumableVec, Consumable};
@@ -47,25 +47,69 @@
 //! });
 //! ```
 
-use std::sync::{Arc, Mutex};
+#[macro_use]
+mod trace;
 
-/// Consume content from a data collection
+mod at;
+mod error;
+#[cfg(feature = "async")]
+mod future;
+mod indexed;
+#[cfg(feature = "journal")]
+mod journal;
+mod metrics;
+mod pattern;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod stats;
+mod subscription;
+mod writer;
+
+pub use at::{AtResponse, ResultCode, UrcRegistry};
+pub use error::{ConsumeError, PoisonPolicy, TryAddError};
+pub use indexed::IndexedConsumableVec;
+#[cfg(feature = "journal")]
+pub use journal::JournaledConsumableVec;
+pub use metrics::MetricsRegistry;
+pub use pattern::{BytePattern, MatchMode, StringPattern};
+pub use stats::Stats;
+pub use subscription::SubscriptionId;
+pub use writer::{LineSplitter, LineTerminator};
+
+use stats::{Counters, DataGuard};
+use subscription::Subscriptions;
+
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, LockResult, Mutex};
+#[cfg(feature = "async")]
+use std::task::Waker;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Consume content from a data collection with exclusive access
 ///
-/// Consumption can be implemented in a mutable or immutable way, or both.
-/// Which implementation needs to be used is dependent on the using application.
+/// This allows to directly manipulate the internal data. Here the caller needs
+/// to take care of ownership.
 ///
+/// Every `Consumable` is a `ConsumableMut` as well, `consume_mut` then simply
+/// forwards to `consume`.
 ///
 /// Example:
 /// In this example, `consume_mut` will take all entries of type u16 which are greater than
 /// the input value
 /// ```
-/// use consumable_vec::Consumable;
+/// use consumable_vec::ConsumableMut;
 ///
 /// struct Example {
 ///     data : Vec<u16>   
 /// }  
 ///
-/// impl Consumable for Example {
+/// impl ConsumableMut for Example {
 ///   type Item = Example;
 ///   type DataType = u16;
 ///
@@ -85,23 +129,46 @@ use std::sync::{Arc, Mutex};
 ///             }
 ///     }
 /// }
-///      
-pub trait Consumable {
+/// ```
+pub trait ConsumableMut {
     type Item;
     type DataType;
 
-    /// # Immutable consume method    
-    /// This shall be implemented for shared access, e.g. when inner Vector
-    /// uses reference counters and mutexes to be changed.
-    fn consume(&self, _pattern: Self::DataType) -> Option<Self::Item> {
-        unimplemented!();
-    }
-
     /// # Mutable consume method    
     /// This allows to directly manipiulate the internal data. Here the caller needs
     /// to take care of ownership
-    fn consume_mut(&mut self, _pattern: Self::DataType) -> Option<Self::Item> {
-        unimplemented!();
+    fn consume_mut(&mut self, pattern: Self::DataType) -> Option<Self::Item>;
+}
+
+/// Consume content from a data collection with shared access
+///
+/// This shall be implemented for shared access, e.g. when inner Vector
+/// uses reference counters and mutexes to be changed.
+///
+/// A plain `ConsumableVec` only offers exclusive access, so trying to consume
+/// from it through a shared reference does not compile:
+/// ```compile_fail
+/// use consumable_vec::{Consumable, ConsumableVec};
+///
+/// let data: ConsumableVec<String> = ConsumableVec::default();
+/// data.consume("pattern".to_string());
+/// ```
+pub trait Consumable {
+    type Item;
+    type DataType;
+
+    /// # Immutable consume method    
+    /// Consumes data through a shared reference, so it can be called from
+    /// multiple owners concurrently
+    fn consume(&self, pattern: Self::DataType) -> Option<Self::Item>;
+}
+
+impl<C: Consumable + ?Sized> ConsumableMut for C {
+    type Item = C::Item;
+    type DataType = C::DataType;
+
+    fn consume_mut(&mut self, pattern: Self::DataType) -> Option<Self::Item> {
+        self.consume(pattern)
     }
 }
 
@@ -111,32 +178,316 @@ pub trait Consumable {
 ///
 /// Consumed data will be removed from the data pool. Consecutive consume calls
 /// with an identical pattern will most likely reurn `None`
-#[derive(Debug, Clone)]
+///
+/// Entries can be given a time to live. Expired entries are dropped before
+/// any consumption, so they can never be matched.
+///
+/// In ring mode the vector holds a limited number of entries, adding to a full
+/// vector evicts the oldest entry.
+#[derive(Clone)]
 pub struct ConsumableVec<T> {
     data: Vec<T>,
+    /// deadline of every entry in `data`, `None` for entries which never expire
+    deadlines: Vec<Option<Instant>>,
+    /// earliest of all `deadlines`, may be outdated after entries got consumed
+    next_deadline: Option<Instant>,
+    ttl: Option<Duration>,
+    on_expired: Option<ExpiredCallback<T>>,
+    ring_capacity: Option<usize>,
+    /// number of entries evicted in ring mode so far
+    evicted: usize,
 }
 
+/// Callback receiving entries which got dropped because their time to live elapsed
+type ExpiredCallback<T> = Arc<dyn Fn(T) + Send + Sync>;
+
 impl<T> ConsumableVec<T> {
-    fn new(data: Option<Vec<T>>) -> Self {
+    pub fn new(data: Option<Vec<T>>) -> Self {
+        let data = data.unwrap_or_default();
+        ConsumableVec {
+            deadlines: data.iter().map(|_| None).collect(),
+            data,
+            next_deadline: None,
+            ttl: None,
+            on_expired: None,
+            ring_capacity: None,
+            evicted: 0,
+        }
+    }
+
+    /// Creates an empty vector in ring mode holding at most `capacity` entries
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn with_ring_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "ring capacity must not be zero");
         ConsumableVec {
-            data: match data {
-                Some(d) => d,
-                None => Vec::new(),
-            },
+            ring_capacity: Some(capacity),
+            ..Self::new(None)
         }
     }
 
-    fn add(&mut self, reply: T) {
+    /// Adds `reply` to the data, evicting the oldest entry if the ring is full
+    pub fn add(&mut self, reply: T) {
+        self.add_evicting(reply);
+    }
+
+    /// Like `add`, but returns the number of entries evicted to make room
+    pub fn add_evicting(&mut self, reply: T) -> usize {
+        let deadline = self.ttl.and_then(|ttl| Instant::now().checked_add(ttl));
+        self.push(reply, deadline)
+    }
+
+    /// Adds `reply` to the data, dropping it after `ttl` if not consumed until then
+    pub fn add_with_ttl(&mut self, reply: T, ttl: Duration) {
+        self.push(reply, Instant::now().checked_add(ttl));
+    }
+
+    /// Adds `reply` and returns the number of entries evicted to make room
+    fn push(&mut self, reply: T, deadline: Option<Instant>) -> usize {
+        self.remove_expired();
+
+        if let Some(deadline) = deadline {
+            self.next_deadline = Some(self.next_deadline.map_or(deadline, |d| d.min(deadline)));
+        }
+        let mut evict = 0;
+        if let Some(capacity) = self.ring_capacity {
+            if self.data.len() >= capacity {
+                evict = self.data.len() + 1 - capacity;
+                self.data.drain(..evict);
+                self.deadlines.drain(..evict);
+                self.evicted += evict;
+            }
+        }
+
         self.data.push(reply);
+        self.deadlines.push(deadline);
+        event!(len = self.data.len(), "added");
+        evict
     }
 
-    fn clear(&mut self) {
+    pub fn clear(&mut self) {
+        event!(removed = self.data.len(), "cleared");
         self.data.clear();
+        self.deadlines.clear();
+        self.next_deadline = None;
+    }
+
+    /// Sets the time to live of entries added afterwards, `None` never expires them
+    pub fn set_ttl(&mut self, ttl: Option<Duration>) {
+        self.ttl = ttl;
+    }
+
+    /// Sets a callback receiving every entry dropped because its time to live elapsed
+    pub fn set_expired_callback<F>(&mut self, on_expired: F)
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.on_expired = Some(Arc::new(on_expired));
+    }
+
+    /// Drops all entries whose time to live elapsed, handing them to the expired callback
+    ///
+    /// Returns the number of dropped entries.
+    pub fn remove_expired(&mut self) -> usize {
+        let now = Instant::now();
+        match self.next_deadline {
+            Some(next_deadline) if next_deadline <= now => {}
+            _ => return 0,
+        }
+
+        let data = std::mem::take(&mut self.data);
+        let deadlines = std::mem::take(&mut self.deadlines);
+        self.next_deadline = None;
+        let mut expired = 0;
+
+        for (entry, deadline) in data.into_iter().zip(deadlines) {
+            match deadline {
+                Some(deadline) if deadline <= now => {
+                    expired += 1;
+                    if let Some(on_expired) = &self.on_expired {
+                        on_expired(entry);
+                    }
+                }
+                _ => {
+                    if let Some(deadline) = deadline {
+                        self.next_deadline =
+                            Some(self.next_deadline.map_or(deadline, |d| d.min(deadline)));
+                    }
+                    self.data.push(entry);
+                    self.deadlines.push(deadline);
+                }
+            }
+        }
+
+        expired
     }
 
     pub fn inner(&self) -> &Vec<T> {
         &self.data
     }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    /// Returns the number of entries evicted in ring mode so far
+    pub fn evicted(&self) -> usize {
+        self.evicted
+    }
+
+    /// # Predicate based peek method
+    /// Returns clones of all entries for which `predicate` returns `true`,
+    /// without removing them
+    pub fn peek_where<F>(&self, mut predicate: F) -> Option<ConsumableVec<T>>
+    where
+        T: Clone,
+        F: FnMut(&T) -> bool,
+    {
+        let peeked = self
+            .unexpired()
+            .filter(|d| predicate(d))
+            .cloned()
+            .collect::<Vec<T>>();
+
+        if !peeked.is_empty() {
+            Some(ConsumableVec::new(Some(peeked)))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if `predicate` returns `true` for any entry
+    pub fn contains_where<F>(&self, predicate: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.unexpired().any(predicate)
+    }
+
+    /// Iterates all entries whose time to live did not elapse yet
+    fn unexpired(&self) -> impl Iterator<Item = &T> {
+        let now = Instant::now();
+        self.data
+            .iter()
+            .zip(&self.deadlines)
+            .filter(move |(_, deadline)| deadline.is_none_or(|d| d > now))
+            .map(|(entry, _)| entry)
+    }
+
+    /// # Predicate based consume method
+    /// Removes all entries for which `predicate` returns `true` and returns them
+    /// in insertion order. Works for any `T`, matched entries are moved, not cloned.
+    ///
+    /// The entries are visited once, kept entries are compacted in place.
+    pub fn consume_where<F>(&mut self, predicate: F) -> Option<ConsumableVec<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.remove_expired();
+        self.extract_where(predicate)
+    }
+
+    /// `consume_where` without removing expired entries first, for callers
+    /// which already picked entries by their index
+    fn extract_where<F>(&mut self, mut predicate: F) -> Option<ConsumableVec<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut compaction = Compaction {
+            deadlines: &mut self.deadlines,
+            visited: 0,
+            kept: 0,
+        };
+        let consumed: Vec<T> = self
+            .data
+            .extract_if(.., |entry| {
+                let matched = predicate(entry);
+                if !matched {
+                    compaction.deadlines[compaction.kept] =
+                        compaction.deadlines[compaction.visited];
+                    compaction.kept += 1;
+                }
+                compaction.visited += 1;
+                matched
+            })
+            .collect();
+        drop(compaction);
+
+        event!(
+            matched = consumed.len(),
+            remaining = self.data.len(),
+            "consumed"
+        );
+        if !consumed.is_empty() {
+            Some(ConsumableVec::new(Some(consumed)))
+        } else {
+            None
+        }
+    }
+
+    /// # Limited predicate based consume method
+    /// Like `consume_where`, but removes at most the `n` oldest matching entries
+    pub fn consume_n_where<F>(&mut self, mut predicate: F, n: usize) -> Option<ConsumableVec<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let mut left = n;
+        self.consume_where(|d| {
+            if left > 0 && predicate(d) {
+                left -= 1;
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// # Single predicate based consume method
+    /// Removes and returns the oldest entry for which `predicate` returns `true`
+    pub fn consume_first_where<F>(&mut self, predicate: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.remove_expired();
+
+        let consumed = self.data.iter().position(predicate).map(|index| {
+            self.deadlines.remove(index);
+            self.data.remove(index)
+        });
+        event!(
+            matched = usize::from(consumed.is_some()),
+            remaining = self.data.len(),
+            "consumed"
+        );
+        consumed
+    }
+}
+
+/// Compacts the deadlines of the entries kept by `consume_where`
+///
+/// Dropping it removes the deadlines of all visited but not kept entries.
+/// This keeps the deadlines aligned with the entries even if the predicate
+/// panics, as the entries not visited yet stay in place then.
+struct Compaction<'a> {
+    deadlines: &'a mut Vec<Option<Instant>>,
+    visited: usize,
+    kept: usize,
+}
+
+impl Drop for Compaction<'_> {
+    fn drop(&mut self) {
+        self.deadlines.drain(self.kept..self.visited);
+    }
+}
+
+// not derived, the expired callback can not be printed
+impl<T: fmt::Debug> fmt::Debug for ConsumableVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsumableVec")
+            .field("data", &self.data)
+            .finish()
+    }
 }
 
 impl<T> len_trait::Len for ConsumableVec<T> {
@@ -151,39 +502,155 @@ impl<T> len_trait::Empty for ConsumableVec<T> {
     }
 }
 
-impl Consumable for ConsumableVec<String> {
+impl ConsumableMut for ConsumableVec<String> {
     type Item = ConsumableVec<String>;
     type DataType = String;
 
     fn consume_mut(&mut self, pattern: Self::DataType) -> Option<Self::Item> {
+        span!("consume_mut", pattern = %pattern);
         let trimmed_pattern = pattern.trim();
 
-        let val = self
-            .data
-            .iter()
-            .filter(|r| r.trim().starts_with(trimmed_pattern))
-            .map(|x| x.to_string())
-            .collect::<Vec<Self::DataType>>();
+        self.consume_where(|r| r.trim().starts_with(trimmed_pattern))
+    }
+}
+
+impl ConsumableVec<String> {
+    /// # Configurable consume method
+    /// Removes and returns all entries matched by `pattern`, allowing other
+    /// match modes than the trimmed prefix matching of `consume_mut`
+    pub fn consume_matching(&mut self, pattern: &StringPattern) -> Option<ConsumableVec<String>> {
+        self.consume_where(|d| pattern.matches(d))
+    }
 
-        // remove all values just consumed
-        // nightly rust has drain_filter which could do
-        // filtering and removal in one step
-        self.data.retain(|d| !d.trim().starts_with(trimmed_pattern));
+    /// # Limited consume method
+    /// Removes and returns at most the `n` oldest entries matching `pattern`,
+    /// using the same trimmed prefix matching as `consume_mut`
+    pub fn consume_n(&mut self, pattern: String, n: usize) -> Option<ConsumableVec<String>> {
+        let pattern = StringPattern::new(pattern);
+        self.consume_n_where(|d| pattern.matches(d), n)
+    }
 
-        if !val.is_empty() {
-            Some(ConsumableVec::new(Some(val)))
-        } else {
-            None
-        }
+    /// # Single consume method
+    /// Removes and returns the oldest entry matching `pattern`,
+    /// using the same trimmed prefix matching as `consume_mut`
+    pub fn consume_first(&mut self, pattern: String) -> Option<String> {
+        let pattern = StringPattern::new(pattern);
+        self.consume_first_where(|d| pattern.matches(d))
+    }
+
+    /// # Peek method
+    /// Returns clones of all entries `consume_mut` would consume for `pattern`,
+    /// without removing them
+    pub fn peek(&self, pattern: String) -> Option<ConsumableVec<String>> {
+        let pattern = StringPattern::new(pattern);
+        self.peek_where(|d| pattern.matches(d))
+    }
+
+    /// Returns `true` if `consume_mut` would consume anything for `pattern`
+    pub fn contains(&self, pattern: String) -> bool {
+        let pattern = StringPattern::new(pattern);
+        self.contains_where(|d| pattern.matches(d))
+    }
+}
+
+#[cfg(feature = "regex")]
+impl ConsumableVec<String> {
+    /// # Regex consume method
+    /// Removes and returns all entries matching `regex`. In contrast to
+    /// `consume_mut`, entries are not trimmed before matching.
+    pub fn consume_regex(&mut self, regex: &regex::Regex) -> Option<ConsumableVec<String>> {
+        self.consume_where(|d| regex.is_match(d))
+    }
+}
+
+impl ConsumableMut for ConsumableVec<Vec<u8>> {
+    type Item = ConsumableVec<Vec<u8>>;
+    type DataType = Vec<u8>;
+
+    /// Consumes all entries starting with `pattern`, ignoring ASCII whitespace
+    /// like the `String` implementation
+    fn consume_mut(&mut self, pattern: Self::DataType) -> Option<Self::Item> {
+        span!("consume_mut", pattern = %String::from_utf8_lossy(&pattern));
+        self.consume_bytes(&BytePattern::new(pattern))
+    }
+}
+
+impl ConsumableVec<Vec<u8>> {
+    /// # Configurable byte consume method
+    /// Removes and returns all entries matched by `pattern`
+    pub fn consume_bytes(&mut self, pattern: &BytePattern) -> Option<ConsumableVec<Vec<u8>>> {
+        self.consume_where(|d| pattern.matches(d))
     }
 }
 
-impl Default for ConsumableVec<String> {
+impl<T> Default for ConsumableVec<T> {
     fn default() -> Self {
         Self::new(None)
     }
 }
 
+// the following traits only regard the entries, not the configuration of
+// the vector like its time to live or ring capacity
+
+impl<T: PartialEq> PartialEq for ConsumableVec<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<T: Eq> Eq for ConsumableVec<T> {}
+
+impl<T: Hash> Hash for ConsumableVec<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+    }
+}
+
+impl<T> From<Vec<T>> for ConsumableVec<T> {
+    fn from(data: Vec<T>) -> Self {
+        Self::new(Some(data))
+    }
+}
+
+impl<T> From<ConsumableVec<T>> for Vec<T> {
+    fn from(vec: ConsumableVec<T>) -> Self {
+        vec.data
+    }
+}
+
+impl<T> FromIterator<T> for ConsumableVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(Some(iter.into_iter().collect()))
+    }
+}
+
+/// Adds every item like `add` does
+impl<T> Extend<T> for ConsumableVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+impl<T> IntoIterator for ConsumableVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a ConsumableVec<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
 /// Generic structure for storing consumable data of type T in a shared Vector
 ///
 /// This implementation is using atomic referenc counting (`Arc`) as well as
@@ -193,145 +660,1191 @@ impl Default for ConsumableVec<String> {
 /// with an identical pattern will most likely return `None`, when no new data got
 /// produced
 ///
+/// Every `add` notifies waiting consumers, so `consume_blocking` can be used
+/// instead of polling `consume` in a loop. With the `async` feature enabled,
+/// `consume_async` offers the same for async tasks.
+///
+/// If a thread panics while accessing the data, the data gets poisoned and
+/// all further calls panic as well. The `try_` methods return a `ConsumeError`
+/// instead, or the data can be recovered by setting `PoisonPolicy::Recover`.
+///
+/// A vector created by `with_capacity_limit` holds at most a given number of
+/// entries. Producers calling `add` on a full vector block until consumers
+/// removed data, while `try_add` hands the item back.
+///
+/// A vector created by `with_ring_capacity` never blocks producers, instead
+/// `add` on a full vector evicts the oldest entry.
 ///
+/// With `set_ttl` or `add_with_ttl`, entries expire after a given duration.
+/// Expired entries are dropped whenever the data is accessed, or periodically
+/// by a thread started with `start_reaper`.
 ///
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct SharedConsumableVec<T> {
-    data: Arc<Mutex<ConsumableVec<T>>>,
+    shared: Arc<Shared<T>>,
+}
+
+/// State shared between all clones of a `SharedConsumableVec`
+#[derive(Debug)]
+struct Shared<T> {
+    data: Mutex<ConsumableVec<T>>,
+    added: Condvar,
+    not_full: Condvar,
+    capacity_limit: Option<usize>,
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
+    subscriptions: Mutex<Subscriptions<T>>,
+    recover_poisoned: AtomicBool,
+    counters: Counters,
 }
 
 impl<T> SharedConsumableVec<T> {
     pub fn new(data: Option<Vec<T>>) -> Self {
+        Self::with_limit(data, None)
+    }
+
+    /// Creates an empty vector holding at most `limit` entries
+    ///
+    /// # Panics
+    /// Panics if `limit` is zero, as no entry could ever be added.
+    pub fn with_capacity_limit(limit: usize) -> Self {
+        assert!(limit > 0, "capacity limit must not be zero");
+        Self::with_limit(None, Some(limit))
+    }
+
+    /// Creates an empty vector holding at most `capacity` entries, where adding
+    /// to a full vector evicts the oldest entry
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn with_ring_capacity(capacity: usize) -> Self {
+        Self::with_data(ConsumableVec::with_ring_capacity(capacity), None)
+    }
+
+    fn with_limit(data: Option<Vec<T>>, capacity_limit: Option<usize>) -> Self {
+        Self::with_data(ConsumableVec::new(data), capacity_limit)
+    }
+
+    fn with_data(data: ConsumableVec<T>, capacity_limit: Option<usize>) -> Self {
+        let counters = Counters::default();
+        counters.record_len(data.data.len());
+
         SharedConsumableVec {
-            data: Arc::new(Mutex::new(ConsumableVec::new(data))),
+            shared: Arc::new(Shared {
+                data: Mutex::new(data),
+                added: Condvar::new(),
+                not_full: Condvar::new(),
+                capacity_limit,
+                #[cfg(feature = "async")]
+                wakers: Mutex::new(Vec::new()),
+                subscriptions: Mutex::new(Subscriptions::new()),
+                recover_poisoned: AtomicBool::new(false),
+                counters,
+            }),
         }
     }
 
+    /// Adds `reply` to the data
+    ///
+    /// If the capacity limit is reached, this blocks until consumers removed data.
     pub fn add(&self, reply: T) {
-        self.data.lock().unwrap().add(reply);
+        self.insert(reply, None);
     }
 
-    pub fn clear(&self) {
-        self.data.lock().unwrap().clear();
+    /// Like `add`, but returns the number of entries evicted in ring mode to
+    /// make room for `reply`
+    ///
+    /// In contrast to comparing `evicted` before and after `add`, the result is
+    /// not affected by clones adding at the same time.
+    pub fn add_evicting(&self, reply: T) -> usize {
+        self.insert(reply, None)
     }
-}
 
-impl<T> len_trait::Len for SharedConsumableVec<T> {
-    fn len(&self) -> usize {
-        self.data.lock().unwrap().len()
+    /// Adds `reply` to the data, dropping it after `ttl` if not consumed until then
+    ///
+    /// This overrides the time to live set by `set_ttl` for this single entry.
+    pub fn add_with_ttl(&self, reply: T, ttl: Duration) {
+        self.insert(reply, Some(ttl));
     }
-}
 
-impl<T> len_trait::Empty for SharedConsumableVec<T> {
-    fn is_empty(&self) -> bool {
-        self.data.lock().unwrap().is_empty()
+    /// Adds `reply` and returns the number of entries evicted to make room
+    fn insert(&self, reply: T, ttl: Option<Duration>) -> usize {
+        self.try_insert(reply, ttl).map_err(|(e, _)| e).unwrap()
     }
-}
 
-impl Consumable for SharedConsumableVec<String> {
-    type Item = ConsumableVec<String>;
-    type DataType = String;
+    /// Like `insert`, but hands `reply` back with the error instead of
+    /// panicking if the vector is poisoned
+    pub(crate) fn try_insert(
+        &self,
+        reply: T,
+        ttl: Option<Duration>,
+    ) -> Result<usize, (ConsumeError, T)> {
+        span!("add");
+        let reply = match self.route(reply)? {
+            Some(reply) => reply,
+            None => return Ok(0),
+        };
 
-    fn consume(&self, pattern: Self::DataType) -> Option<Self::Item> {
-        self.data.lock().unwrap().consume_mut(pattern)
+        let mut guard = match self.lock() {
+            Ok(guard) => guard,
+            Err(e) => return Err((e, reply)),
+        };
+        while self.is_full(&guard) {
+            // wake up when the next entry expires, making room without a consumer
+            let timeout = guard
+                .next_deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            let waited = guard.wait(&self.shared.not_full, timeout);
+            guard = match self.unpoison(waited) {
+                Ok(guard) => guard,
+                Err(e) => return Err((e, reply)),
+            };
+            self.expire(&mut guard);
+        }
+        let deadline = ttl
+            .or(guard.ttl)
+            .and_then(|ttl| Instant::now().checked_add(ttl));
+        let evicted = guard.push(reply, deadline);
+        self.shared.counters.record_add(guard.data.len());
+        drop(guard);
+
+        self.notify_added();
+        Ok(evicted)
     }
-}
 
-impl Default for SharedConsumableVec<String> {
-    fn default() -> Self {
-        Self::new(None)
+    /// # Fallible add method
+    /// Like `add`, but hands `reply` back instead of panicking or blocking
+    pub fn try_add(&self, reply: T) -> Result<(), TryAddError<T>> {
+        span!("add");
+        let reply = match self.route(reply) {
+            Ok(Some(reply)) => reply,
+            Ok(None) => return Ok(()),
+            Err((_, reply)) => return Err(TryAddError::Poisoned(reply)),
+        };
+
+        let mut guard = match self.lock() {
+            Ok(guard) => guard,
+            Err(_) => return Err(TryAddError::Poisoned(reply)),
+        };
+        if self.is_full(&guard) {
+            return Err(TryAddError::Full(reply));
+        }
+        guard.add(reply);
+        self.shared.counters.record_add(guard.data.len());
+        drop(guard);
+
+        self.notify_added();
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod test_at_replies {
-    use super::*;
-    use len_trait::Len;
+    /// # Subscribe method
+    /// Registers a standing subscription: every entry added afterwards for which
+    /// `predicate` returns `true` is handed to `sink` instead of being stored.
+    ///
+    /// If several subscriptions match, the oldest one receives the entry.
+    /// `sink` is called from the adding thread and must not access this vector.
+    pub fn subscribe_where<P, S>(&self, predicate: P, sink: S) -> SubscriptionId
+    where
+        P: Fn(&T) -> bool + Send + 'static,
+        S: FnMut(T) + Send + 'static,
+    {
+        self.unpoison(self.shared.subscriptions.lock())
+            .unwrap()
+            .subscribe(Box::new(predicate), Box::new(sink))
+    }
 
-    #[test]
-    fn consume_when_pattern_not_in_replies_should_return_none() {
-        let mut at = ConsumableVec::default();
-        at.add("data".to_string());
-        assert!(at.consume_mut("pattern".to_string()).is_none());
+    /// Removes a subscription, returns `false` if it was not registered
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        self.unpoison(self.shared.subscriptions.lock())
+            .unwrap()
+            .unsubscribe(id)
     }
 
-    #[test]
-    fn consume_when_pattern_in_replies_should_return_some() {
-        let mut at = ConsumableVec::default();
-        at.add("data".to_string());
-        assert!(at.consume_mut("da".to_string()).is_some());
+    /// Hands `reply` to a matching subscription, or returns it back
+    ///
+    /// If the subscriptions are poisoned, `reply` is handed back with the error.
+    fn route(&self, reply: T) -> Result<Option<T>, (ConsumeError, T)> {
+        let mut subscriptions = match self.unpoison(self.shared.subscriptions.lock()) {
+            Ok(subscriptions) => subscriptions,
+            Err(e) => return Err((e, reply)),
+        };
+        let reply = subscriptions.route(reply);
+        if reply.is_none() {
+            event!("routed to subscription");
+            self.shared.counters.record_routed();
+        }
+        Ok(reply)
     }
 
-    #[test]
-    fn consume_when_pattern_in_replies_should_have_data() {
-        let mut at = ConsumableVec::default();
-        at.add("data".to_string());
-        at.add("ata".to_string());
-        let consumed = at.consume_mut("da".to_string()).unwrap();
-        assert_eq!(1, consumed.len());
-        assert_eq!("data".to_string(), consumed.data[0]);
+    pub fn capacity_limit(&self) -> Option<usize> {
+        self.shared.capacity_limit
     }
 
-    #[test]
-    fn consume_when_pattern_in_replies_multiple_times_should_have_data_multipletimes() {
-        let mut at = ConsumableVec::default();
-        at.add("data".to_string());
-        at.add("ata".to_string());
-        at.add("data2".to_string());
-        let consumed = at.consume_mut("da".to_string()).unwrap();
-        assert_eq!(2, consumed.len());
-        assert_eq!("data2".to_string(), consumed.data[1]);
+    /// Returns the number of entries evicted in ring mode so far
+    pub fn evicted(&self) -> usize {
+        self.lock().unwrap().evicted()
     }
 
-    #[test]
-    fn consume_should_remove_values_from_data() {
-        let mut at = ConsumableVec::default();
-        at.add("data".to_string());
-        at.add("ata".to_string());
-        at.add("data2".to_string());
-        assert_eq!(3, at.len());
-        let _ = at.consume_mut("da".to_string()).unwrap();
-        assert_eq!(1, at.len());
+    fn is_full(&self, data: &ConsumableVec<T>) -> bool {
+        self.shared
+            .capacity_limit
+            .is_some_and(|limit| data.data.len() >= limit)
     }
-}
 
-#[cfg(test)]
-mod test_shared_at_replies {
+    fn notify_added(&self) {
+        self.shared.added.notify_all();
 
-    use super::*;
-    use len_trait::Len;
+        #[cfg(feature = "async")]
+        for waker in self.shared.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
 
-    #[test]
-    fn consume_when_pattern_not_in_replies_should_return_none() {
-        let at = SharedConsumableVec::default();
-        at.add("data".to_string());
-        assert!(at.consume("pattern".to_string()).is_none());
+    /// Wakes producers blocked on a full vector, to be called after data got removed
+    fn notify_removed(&self) {
+        if self.shared.capacity_limit.is_some() {
+            self.shared.not_full.notify_all();
+        }
     }
 
-    #[test]
-    fn consume_when_pattern_in_replies_should_return_some() {
-        let at = SharedConsumableVec::default();
-        at.add("data".to_string());
-        assert!(at.consume("da".to_string()).is_some());
+    /// Applies `consume` to the inner data, waking blocked producers afterwards
+    fn try_consume_with<R, F>(&self, consume: F) -> Result<R, ConsumeError>
+    where
+        F: FnOnce(&mut ConsumableVec<T>) -> R,
+    {
+        let mut guard = self.lock()?;
+        let len = guard.data.len();
+        let consumed = consume(&mut guard);
+        self.shared.counters.record_consume(len - guard.data.len());
+        drop(guard);
+
+        self.notify_removed();
+        Ok(consumed)
     }
 
-    #[test]
-    fn consume_when_pattern_in_replies_should_have_data() {
-        let at = SharedConsumableVec::default();
-        at.add("data".to_string());
-        at.add("ata".to_string());
-        let consumed = at.consume("da".to_string()).unwrap();
-        assert_eq!(1, consumed.len());
-        assert_eq!("data".to_string(), consumed.data[0]);
+    fn consume_with<R, F>(&self, consume: F) -> R
+    where
+        F: FnOnce(&mut ConsumableVec<T>) -> R,
+    {
+        self.try_consume_with(consume).unwrap()
     }
 
-    #[test]
-    fn consume_when_pattern_in_replies_multiple_times_should_have_data_multipletimes() {
-        let at = SharedConsumableVec::default();
-        at.add("data".to_string());
-        at.add("ata".to_string());
-        at.add("data2".to_string());
-        let consumed = at.consume("da".to_string()).unwrap();
+    /// # Fallible len method
+    /// Like `len`, but returns an error instead of panicking
+    pub fn try_len(&self) -> Result<usize, ConsumeError> {
+        Ok(self.lock()?.data.len())
+    }
+
+    /// Sets how poisoned data is treated, for all clones of this vector
+    pub fn set_poison_policy(&self, policy: PoisonPolicy) {
+        self.shared
+            .recover_poisoned
+            .store(policy == PoisonPolicy::Recover, Ordering::Relaxed);
+    }
+
+    pub fn poison_policy(&self) -> PoisonPolicy {
+        if self.shared.recover_poisoned.load(Ordering::Relaxed) {
+            PoisonPolicy::Recover
+        } else {
+            PoisonPolicy::Fail
+        }
+    }
+
+    /// Sets the time to live of entries added afterwards, `None` never expires them
+    pub fn set_ttl(&self, ttl: Option<Duration>) {
+        self.lock().unwrap().set_ttl(ttl);
+    }
+
+    /// Sets a callback receiving every entry dropped because its time to live elapsed
+    ///
+    /// The callback is called while the data is locked, so it must not access
+    /// this vector.
+    pub fn set_expired_callback<F>(&self, on_expired: F)
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        self.lock().unwrap().set_expired_callback(on_expired);
+    }
+
+    /// Drops all entries whose time to live elapsed and returns their number
+    pub fn remove_expired(&self) -> usize {
+        let mut guard = self.lock_data().unwrap();
+        self.expire(&mut guard)
+    }
+
+    /// Starts a thread dropping expired entries every `interval`
+    ///
+    /// The thread ends as soon as all clones of this vector got dropped.
+    pub fn start_reaper(&self, interval: Duration) -> JoinHandle<()>
+    where
+        T: Send + 'static,
+    {
+        let shared = Arc::downgrade(&self.shared);
+
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+
+            let vec = match shared.upgrade() {
+                Some(shared) => SharedConsumableVec { shared },
+                None => break,
+            };
+            match vec.lock_data() {
+                Ok(mut guard) => vec.expire(&mut guard),
+                Err(_) => break,
+            };
+        })
+    }
+
+    fn expire(&self, data: &mut ConsumableVec<T>) -> usize {
+        let expired = data.remove_expired();
+        if expired > 0 {
+            self.notify_removed();
+        }
+        expired
+    }
+
+    /// Returns counters describing the usage of this vector and all its clones
+    pub fn stats(&self) -> Stats {
+        let len = self.lock().unwrap().data.len();
+        self.shared.counters.snapshot(len)
+    }
+
+    /// Locks the inner data, dropping expired entries
+    fn lock(&self) -> Result<DataGuard<'_, T>, ConsumeError> {
+        let mut guard = self.lock_data()?;
+        self.expire(&mut guard);
+        Ok(guard)
+    }
+
+    fn lock_data(&self) -> Result<DataGuard<'_, T>, ConsumeError> {
+        self.unpoison(DataGuard::new(
+            self.shared.data.lock(),
+            &self.shared.counters,
+        ))
+    }
+
+    /// Applies the poison policy to the result of locking the inner data
+    fn unpoison<G>(&self, result: LockResult<G>) -> Result<G, ConsumeError> {
+        match result {
+            Ok(guard) => Ok(guard),
+            Err(poisoned) if self.poison_policy() == PoisonPolicy::Recover => {
+                Ok(poisoned.into_inner())
+            }
+            Err(_) => Err(ConsumeError::Poisoned),
+        }
+    }
+
+    /// Repeatedly applies `consume` to the inner data until it returns `Some`
+    ///
+    /// Between two attempts the calling thread sleeps until the next `add`.
+    /// Returns `None` if `timeout` elapses without a successful attempt.
+    fn wait_for<R, F>(&self, timeout: Duration, mut consume: F) -> Option<R>
+    where
+        F: FnMut(&mut ConsumableVec<T>) -> Option<R>,
+    {
+        // a timeout too large to be represented is treated as infinite
+        let deadline = Instant::now().checked_add(timeout);
+        let mut guard = self.lock().unwrap();
+
+        loop {
+            let len = guard.data.len();
+            if let Some(consumed) = consume(&mut guard) {
+                self.shared.counters.record_consume(len - guard.data.len());
+                drop(guard);
+                self.notify_removed();
+                return Some(consumed);
+            }
+
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        self.shared.counters.record_consume(0);
+                        return None;
+                    }
+                    Some(deadline - now)
+                }
+                None => None,
+            };
+            let waited = guard.wait(&self.shared.added, timeout);
+            guard = self.unpoison(waited).unwrap();
+        }
+    }
+
+    pub fn clear(&self) {
+        span!("clear");
+        let mut guard = self.lock().unwrap();
+        self.shared.counters.record_clear(guard.data.len());
+        guard.clear();
+        drop(guard);
+
+        self.notify_removed();
+    }
+
+    /// # Predicate based consume method
+    /// Removes all entries for which `predicate` returns `true`, see
+    /// `ConsumableVec::consume_where`
+    pub fn consume_where<F>(&self, predicate: F) -> Option<ConsumableVec<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.consume_with(|data| data.consume_where(predicate))
+    }
+
+    /// # Limited predicate based consume method
+    /// Removes at most the `n` oldest matching entries, see
+    /// `ConsumableVec::consume_n_where`
+    pub fn consume_n_where<F>(&self, predicate: F, n: usize) -> Option<ConsumableVec<T>>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.consume_with(|data| data.consume_n_where(predicate, n))
+    }
+
+    /// # Single predicate based consume method
+    /// Removes the oldest matching entry, see `ConsumableVec::consume_first_where`
+    pub fn consume_first_where<F>(&self, predicate: F) -> Option<T>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.consume_with(|data| data.consume_first_where(predicate))
+    }
+
+    /// # Predicate based peek method
+    /// Returns clones of all matching entries without removing them, see
+    /// `ConsumableVec::peek_where`
+    pub fn peek_where<F>(&self, predicate: F) -> Option<ConsumableVec<T>>
+    where
+        T: Clone,
+        F: FnMut(&T) -> bool,
+    {
+        self.lock().unwrap().peek_where(predicate)
+    }
+
+    /// Returns `true` if `predicate` returns `true` for any entry
+    pub fn contains_where<F>(&self, predicate: F) -> bool
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.lock().unwrap().contains_where(predicate)
+    }
+}
+
+// not derived, sharing the data must not require `T: Clone`
+impl<T> Clone for SharedConsumableVec<T> {
+    fn clone(&self) -> Self {
+        SharedConsumableVec {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> len_trait::Len for SharedConsumableVec<T> {
+    fn len(&self) -> usize {
+        self.try_len().unwrap()
+    }
+}
+
+impl<T> len_trait::Empty for SharedConsumableVec<T> {
+    fn is_empty(&self) -> bool {
+        self.lock().unwrap().is_empty()
+    }
+}
+
+impl Consumable for SharedConsumableVec<String> {
+    type Item = ConsumableVec<String>;
+    type DataType = String;
+
+    fn consume(&self, pattern: Self::DataType) -> Option<Self::Item> {
+        span!("consume", pattern = %pattern);
+        self.try_consume(pattern).unwrap()
+    }
+}
+
+impl SharedConsumableVec<String> {
+    /// # Fallible consume method
+    /// Like `consume`, but returns an error instead of panicking
+    pub fn try_consume(
+        &self,
+        pattern: String,
+    ) -> Result<Option<ConsumableVec<String>>, ConsumeError> {
+        self.try_consume_with(|data| data.consume_mut(pattern))
+    }
+
+    /// # Blocking consume method
+    /// Waits until data matching `pattern` got added and consumes it.
+    /// Returns `None` if nothing matched within `timeout`.
+    pub fn consume_blocking(
+        &self,
+        pattern: String,
+        timeout: Duration,
+    ) -> Option<ConsumableVec<String>> {
+        self.wait_for(timeout, |data| data.consume_mut(pattern.clone()))
+    }
+
+    /// # Subscribe method
+    /// Registers a standing subscription for entries matching `pattern`, using
+    /// the same trimmed prefix matching as `consume`, see `subscribe_where`
+    pub fn subscribe<S>(&self, pattern: String, sink: S) -> SubscriptionId
+    where
+        S: FnMut(String) + Send + 'static,
+    {
+        let pattern = StringPattern::new(pattern);
+        self.subscribe_where(move |d: &String| pattern.matches(d), sink)
+    }
+
+    /// # Configurable consume method
+    /// Removes and returns all entries matched by `pattern`, see
+    /// `ConsumableVec::consume_matching`
+    pub fn consume_matching(&self, pattern: &StringPattern) -> Option<ConsumableVec<String>> {
+        self.consume_with(|data| data.consume_matching(pattern))
+    }
+
+    /// # Limited consume method
+    /// Removes at most the `n` oldest entries matching `pattern`, see
+    /// `ConsumableVec::consume_n`
+    pub fn consume_n(&self, pattern: String, n: usize) -> Option<ConsumableVec<String>> {
+        self.consume_with(|data| data.consume_n(pattern, n))
+    }
+
+    /// # Single consume method
+    /// Removes the oldest entry matching `pattern`, see `ConsumableVec::consume_first`
+    pub fn consume_first(&self, pattern: String) -> Option<String> {
+        self.consume_with(|data| data.consume_first(pattern))
+    }
+
+    /// # Peek method
+    /// Returns clones of all entries `consume` would consume for `pattern`,
+    /// without removing them
+    pub fn peek(&self, pattern: String) -> Option<ConsumableVec<String>> {
+        self.lock().unwrap().peek(pattern)
+    }
+
+    /// Returns `true` if `consume` would consume anything for `pattern`
+    pub fn contains(&self, pattern: String) -> bool {
+        self.lock().unwrap().contains(pattern)
+    }
+
+    /// # Async consume method
+    /// Resolves as soon as data matching `pattern` got added and consumes it.
+    ///
+    /// The returned future does not borrow `self`, so it can be moved into a
+    /// spawned task. Dropping it before completion consumes nothing.
+    #[cfg(feature = "async")]
+    pub fn consume_async(&self, pattern: String) -> impl Future<Output = ConsumableVec<String>> {
+        future::WaitFor::new(self.clone(), move |data: &mut ConsumableVec<String>| {
+            data.consume_mut(pattern.clone())
+        })
+    }
+}
+
+#[cfg(feature = "regex")]
+impl SharedConsumableVec<String> {
+    /// # Regex consume method
+    /// Removes and returns all entries matching `regex`, see
+    /// `ConsumableVec::consume_regex`
+    pub fn consume_regex(&self, regex: &regex::Regex) -> Option<ConsumableVec<String>> {
+        self.consume_with(|data| data.consume_regex(regex))
+    }
+}
+
+impl Consumable for SharedConsumableVec<Vec<u8>> {
+    type Item = ConsumableVec<Vec<u8>>;
+    type DataType = Vec<u8>;
+
+    fn consume(&self, pattern: Self::DataType) -> Option<Self::Item> {
+        span!("consume", pattern = %String::from_utf8_lossy(&pattern));
+        self.consume_with(|data| data.consume_mut(pattern))
+    }
+}
+
+impl SharedConsumableVec<Vec<u8>> {
+    /// # Configurable byte consume method
+    /// Removes and returns all entries matched by `pattern`, see
+    /// `ConsumableVec::consume_bytes`
+    pub fn consume_bytes(&self, pattern: &BytePattern) -> Option<ConsumableVec<Vec<u8>>> {
+        self.consume_with(|data| data.consume_bytes(pattern))
+    }
+}
+
+impl<T> Default for SharedConsumableVec<T> {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[cfg(test)]
+mod test_at_replies {
+    use super::*;
+    use len_trait::Len;
+
+    #[test]
+    fn consume_when_pattern_not_in_replies_should_return_none() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        assert!(at.consume_mut("pattern".to_string()).is_none());
+    }
+
+    #[test]
+    fn consume_when_pattern_in_replies_should_return_some() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        assert!(at.consume_mut("da".to_string()).is_some());
+    }
+
+    #[test]
+    fn consume_when_pattern_in_replies_should_have_data() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        let consumed = at.consume_mut("da".to_string()).unwrap();
+        assert_eq!(1, consumed.len());
+        assert_eq!("data".to_string(), consumed.data[0]);
+    }
+
+    #[test]
+    fn consume_when_pattern_in_replies_multiple_times_should_have_data_multipletimes() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        at.add("data2".to_string());
+        let consumed = at.consume_mut("da".to_string()).unwrap();
+        assert_eq!(2, consumed.len());
+        assert_eq!("data2".to_string(), consumed.data[1]);
+    }
+
+    #[test]
+    fn consume_should_remove_values_from_data() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        at.add("data2".to_string());
+        assert_eq!(3, at.len());
+        let _ = at.consume_mut("da".to_string()).unwrap();
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn consume_matching_should_remove_matching_values_from_data() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        at.add("DATA".to_string());
+        let pattern = StringPattern::new("ATA")
+            .mode(MatchMode::Suffix)
+            .case_insensitive(true);
+        let consumed = at.consume_matching(&pattern).unwrap();
+        assert_eq!(3, consumed.len());
+        assert_eq!(0, at.len());
+    }
+
+    #[test]
+    fn consume_first_should_remove_only_oldest_match() {
+        let mut at = ConsumableVec::default();
+        at.add("data1".to_string());
+        at.add("ata".to_string());
+        at.add("data2".to_string());
+        assert_eq!(
+            Some("data1".to_string()),
+            at.consume_first("da".to_string())
+        );
+        assert_eq!(2, at.len());
+        assert_eq!("data2".to_string(), at.data[1]);
+    }
+
+    #[test]
+    fn consume_n_should_remove_at_most_n_oldest_matches() {
+        let mut at = ConsumableVec::default();
+        at.add("data1".to_string());
+        at.add("data2".to_string());
+        at.add("ata".to_string());
+        at.add("data3".to_string());
+        let consumed = at.consume_n("da".to_string(), 2).unwrap();
+        assert_eq!(
+            vec!["data1".to_string(), "data2".to_string()],
+            consumed.data
+        );
+        assert_eq!(vec!["ata".to_string(), "data3".to_string()], at.data);
+    }
+
+    #[test]
+    fn peek_should_not_remove_values_from_data() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        let peeked = at.peek("da".to_string()).unwrap();
+        assert_eq!(vec!["data".to_string()], peeked.data);
+        assert_eq!(2, at.len());
+        assert!(at.contains("at".to_string()));
+        assert!(!at.contains("pattern".to_string()));
+        assert!(at.peek("pattern".to_string()).is_none());
+    }
+
+    #[test]
+    fn peek_should_skip_expired_values() {
+        let mut at = ConsumableVec::default();
+        at.add_with_ttl("data".to_string(), Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(20));
+        assert!(at.peek("da".to_string()).is_none());
+        assert!(!at.contains("da".to_string()));
+    }
+
+    #[test]
+    fn consume_n_when_n_is_zero_should_return_none() {
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        assert!(at.consume_n("da".to_string(), 0).is_none());
+        assert_eq!(1, at.len());
+    }
+}
+
+#[cfg(all(test, feature = "regex"))]
+mod test_regex_replies {
+    use super::*;
+    use len_trait::Len;
+    use regex::Regex;
+
+    #[test]
+    fn consume_regex_when_regex_not_matching_should_return_none() {
+        let mut at = ConsumableVec::default();
+        at.add("+CSQ: 20,99".to_string());
+        let regex = Regex::new(r"^\+C(REG|GREG): \d").unwrap();
+        assert!(at.consume_regex(&regex).is_none());
+    }
+
+    #[test]
+    fn consume_regex_should_remove_matching_values_from_data() {
+        let mut at = ConsumableVec::default();
+        at.add("+CREG: 1".to_string());
+        at.add("+CSQ: 20,99".to_string());
+        at.add("+CGREG: 5".to_string());
+        let regex = Regex::new(r"^\+C(REG|GREG): \d").unwrap();
+        let consumed = at.consume_regex(&regex).unwrap();
+        assert_eq!(2, consumed.len());
+        assert_eq!("+CGREG: 5".to_string(), consumed.data[1]);
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn shared_consume_regex_should_remove_matching_values_from_data() {
+        let at = SharedConsumableVec::default();
+        at.add("+CREG: 1".to_string());
+        at.add("OK".to_string());
+        let regex = Regex::new(r"^\+C(REG|GREG): \d").unwrap();
+        let consumed = at.consume_regex(&regex).unwrap();
+        assert_eq!(1, consumed.len());
+        assert_eq!(1, at.len());
+    }
+}
+
+#[cfg(test)]
+mod test_byte_replies {
+    use super::*;
+    use len_trait::Len;
+
+    #[test]
+    fn consume_when_pattern_not_in_replies_should_return_none() {
+        let mut at = ConsumableVec::new(None);
+        at.add(vec![0x02, 0xff, 0x03]);
+        assert!(at.consume_mut(vec![0x03]).is_none());
+    }
+
+    #[test]
+    fn consume_should_ignore_ascii_whitespace() {
+        let mut at = ConsumableVec::new(None);
+        at.add(b"\r\n\x02\xffdata".to_vec());
+        at.add(vec![0x03, 0x02]);
+        let consumed = at.consume_mut(b" \x02\xff".to_vec()).unwrap();
+        assert_eq!(vec![b"\r\n\x02\xffdata".to_vec()], consumed.data);
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn shared_consume_bytes_without_trim_should_respect_whitespace() {
+        let at = SharedConsumableVec::new(None);
+        at.add(b"\r\n\x02".to_vec());
+        at.add(b"\x02\x03".to_vec());
+        let consumed = at
+            .consume_bytes(&BytePattern::new(vec![0x02]).trim(false))
+            .unwrap();
+        assert_eq!(vec![b"\x02\x03".to_vec()], consumed.data);
+        assert_eq!(1, at.len());
+    }
+}
+
+#[cfg(test)]
+mod test_stats {
+    use super::*;
+
+    #[test]
+    fn stats_should_count_adds_consumes_and_clears() {
+        let at = SharedConsumableVec::default();
+        at.add("data1".to_string());
+        at.add("data2".to_string());
+        at.add("ata".to_string());
+        let _ = at.consume("da".to_string()).unwrap();
+        assert!(at.consume("pattern".to_string()).is_none());
+        assert!(at
+            .consume_blocking("pattern".to_string(), Duration::from_millis(1))
+            .is_none());
+        at.add("data3".to_string());
+        at.clear();
+
+        let stats = at.stats();
+        assert_eq!(4, stats.added);
+        assert_eq!(2, stats.consumed);
+        assert_eq!(2, stats.cleared);
+        assert_eq!(0, stats.len);
+        assert_eq!(3, stats.high_water_mark);
+        assert_eq!(2, stats.empty_consumes);
+        assert!(stats.average_lock_hold_time < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn stats_should_count_routed_values_as_added_and_consumed() {
+        let at = SharedConsumableVec::new(Some(vec!["data".to_string(), "ata".to_string()]));
+        at.subscribe("+CREG".to_string(), |_| {});
+        at.add("+CREG: 1".to_string());
+
+        let stats = at.stats();
+        assert_eq!(1, stats.added);
+        assert_eq!(1, stats.consumed);
+        assert_eq!(2, stats.len);
+        assert_eq!(2, stats.high_water_mark);
+    }
+}
+
+#[cfg(test)]
+mod test_subscribed_replies {
+    use super::*;
+    use len_trait::Len;
+    use std::sync::mpsc;
+
+    #[test]
+    fn add_when_subscribed_should_route_matches_to_sink() {
+        let at = SharedConsumableVec::default();
+        let (sender, receiver) = mpsc::channel();
+        at.subscribe("+CREG".to_string(), move |r| sender.send(r).unwrap());
+        at.add("+CREG: 1".to_string());
+        at.add("OK".to_string());
+        assert_eq!(Ok("+CREG: 1".to_string()), receiver.try_recv());
+        assert_eq!(1, at.len());
+        assert!(at.consume("+CREG".to_string()).is_none());
+    }
+
+    #[test]
+    fn try_add_when_sink_panicked_should_hand_back_item() {
+        let at = SharedConsumableVec::default();
+        at.subscribe("+CREG".to_string(), |_| panic!("sink panics"));
+        let adder = at.clone();
+        let _ = thread::spawn(move || adder.add("+CREG: 1".to_string())).join();
+
+        assert_eq!(
+            Err(TryAddError::Poisoned("+CREG: 2".to_string())),
+            at.try_add("+CREG: 2".to_string())
+        );
+    }
+
+    #[test]
+    fn add_when_multiple_subscriptions_match_should_route_to_oldest() {
+        let at = SharedConsumableVec::default();
+        let (first, first_receiver) = mpsc::channel();
+        let (second, second_receiver) = mpsc::channel();
+        at.subscribe_where(
+            |r: &String| r.contains("data"),
+            move |r| first.send(r).unwrap(),
+        );
+        at.subscribe("da".to_string(), move |r| second.send(r).unwrap());
+        at.add("data".to_string());
+        assert_eq!(Ok("data".to_string()), first_receiver.try_recv());
+        assert!(second_receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn add_when_unsubscribed_should_store_value() {
+        let at = SharedConsumableVec::default();
+        let (sender, receiver) = mpsc::channel();
+        let id = at.subscribe("da".to_string(), move |r| sender.send(r).unwrap());
+        assert!(at.unsubscribe(id));
+        assert!(!at.unsubscribe(id));
+        assert_eq!(Ok(()), at.try_add("data".to_string()));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(1, at.len());
+    }
+}
+
+#[cfg(test)]
+mod test_ring_replies {
+    use super::*;
+    use len_trait::Len;
+
+    #[test]
+    fn add_when_ring_full_should_evict_oldest_values() {
+        let mut at = ConsumableVec::with_ring_capacity(2);
+        at.add("data1".to_string());
+        at.add("data2".to_string());
+        at.add("data3".to_string());
+        assert_eq!(vec!["data2".to_string(), "data3".to_string()], at.data);
+        assert_eq!(1, at.evicted());
+    }
+
+    #[test]
+    fn add_evicting_should_return_values_evicted_by_this_call() {
+        let mut at = ConsumableVec::with_ring_capacity(1);
+        assert_eq!(0, at.add_evicting("data1".to_string()));
+        assert_eq!(1, at.add_evicting("data2".to_string()));
+
+        let at = SharedConsumableVec::with_ring_capacity(2);
+        assert_eq!(0, at.add_evicting("data1".to_string()));
+        assert_eq!(0, at.add_evicting("data2".to_string()));
+        at.add("data3".to_string());
+        assert_eq!(1, at.add_evicting("data4".to_string()));
+        assert_eq!(2, at.evicted());
+    }
+
+    #[test]
+    fn add_when_ring_not_full_should_not_evict() {
+        let mut at = ConsumableVec::with_ring_capacity(2);
+        at.add("data1".to_string());
+        let _ = at.consume_mut("da".to_string()).unwrap();
+        at.add("data2".to_string());
+        at.add("data3".to_string());
+        assert_eq!(2, at.len());
+        assert_eq!(0, at.evicted());
+    }
+
+    #[test]
+    fn shared_add_when_ring_full_should_not_block() {
+        let at = SharedConsumableVec::with_ring_capacity(1);
+        at.add("data1".to_string());
+        at.add("data2".to_string());
+        at.add("data3".to_string());
+        assert_eq!(Ok(()), at.try_add("data4".to_string()));
+        assert_eq!(3, at.evicted());
+        let consumed = at.consume("da".to_string()).unwrap();
+        assert_eq!(vec!["data4".to_string()], consumed.data);
+    }
+}
+
+#[cfg(test)]
+mod test_expiring_replies {
+    use super::*;
+    use len_trait::Len;
+
+    #[test]
+    fn consume_when_ttl_elapsed_should_return_none() {
+        let mut at = ConsumableVec::default();
+        at.set_ttl(Some(Duration::from_millis(10)));
+        at.add("data".to_string());
+        thread::sleep(Duration::from_millis(20));
+        assert!(at.consume_mut("da".to_string()).is_none());
+        assert_eq!(0, at.len());
+    }
+
+    #[test]
+    fn consume_when_ttl_not_elapsed_should_return_some() {
+        let mut at = ConsumableVec::default();
+        at.set_ttl(Some(Duration::from_secs(60)));
+        at.add("data".to_string());
+        at.add_with_ttl("data2".to_string(), Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(20));
+        let consumed = at.consume_mut("da".to_string()).unwrap();
+        assert_eq!(vec!["data".to_string()], consumed.data);
+    }
+
+    #[test]
+    fn consume_where_should_keep_deadlines_of_remaining_values() {
+        let mut at = ConsumableVec::default();
+        at.add_with_ttl("data1".to_string(), Duration::from_millis(10));
+        at.add("ata1".to_string());
+        at.add_with_ttl("data2".to_string(), Duration::from_secs(60));
+        at.add_with_ttl("ata2".to_string(), Duration::from_millis(10));
+        at.add_with_ttl("ata3".to_string(), Duration::from_secs(60));
+        let consumed = at.consume_where(|d| d.starts_with("da")).unwrap();
+        assert_eq!(
+            vec!["data1".to_string(), "data2".to_string()],
+            consumed.data
+        );
+
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(1, at.remove_expired());
+        assert_eq!(&vec!["ata1".to_string(), "ata3".to_string()], at.inner());
+    }
+
+    #[test]
+    fn consume_where_when_predicate_panics_should_keep_deadlines_aligned() {
+        let at = SharedConsumableVec::default();
+        at.set_poison_policy(PoisonPolicy::Recover);
+        at.add_with_ttl("a1".to_string(), Duration::from_secs(3600));
+        at.add_with_ttl("b".to_string(), Duration::from_millis(50));
+        at.add_with_ttl("c".to_string(), Duration::from_secs(3600));
+        at.add_with_ttl("d".to_string(), Duration::from_secs(3600));
+
+        let panicking = at.clone();
+        let _ = thread::spawn(move || {
+            panicking.consume_where(|d| {
+                assert_ne!("c", d, "predicate panics");
+                d.starts_with('a')
+            })
+        })
+        .join();
+
+        thread::sleep(Duration::from_millis(60));
+        let consumed = at.consume_where(|_| true).unwrap();
+        assert_eq!(vec!["c".to_string(), "d".to_string()], consumed.data);
+    }
+
+    #[test]
+    fn shared_len_should_drop_expired_values_and_report_them() {
+        let expired = Arc::new(Mutex::new(Vec::new()));
+        let at = SharedConsumableVec::default();
+        let sink = Arc::clone(&expired);
+        at.set_expired_callback(move |e| sink.lock().unwrap().push(e));
+        at.add_with_ttl("data".to_string(), Duration::from_millis(10));
+        at.add("ata".to_string());
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(1, at.len());
+        assert_eq!(vec!["data".to_string()], *expired.lock().unwrap());
+    }
+
+    #[test]
+    fn reaper_should_drop_expired_values_and_end_with_vector() {
+        let expired = Arc::new(Mutex::new(Vec::new()));
+        let at = SharedConsumableVec::default();
+        let sink = Arc::clone(&expired);
+        at.set_expired_callback(move |e| sink.lock().unwrap().push(e));
+        at.set_ttl(Some(Duration::from_millis(10)));
+        at.add("data".to_string());
+        let reaper = at.start_reaper(Duration::from_millis(5));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(vec!["data".to_string()], *expired.lock().unwrap());
+        drop(at);
+        reaper.join().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod test_collection_traits {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn collected_vec_should_convert_back_into_vec() {
+        let at: ConsumableVec<u16> = (1..4).collect();
+        assert_eq!(vec![1, 2, 3], Vec::from(at.clone()));
+        assert_eq!(ConsumableVec::from(vec![1, 2, 3]), at);
+        assert_eq!(vec![2, 4, 6], at.iter().map(|d| d * 2).collect::<Vec<_>>());
+        assert_eq!(6, (&at).into_iter().sum::<u16>());
+        assert_eq!(6, at.into_iter().sum::<u16>());
+    }
+
+    #[test]
+    fn extend_should_respect_ring_capacity() {
+        let mut at = ConsumableVec::with_ring_capacity(2);
+        at.extend(vec!["data1", "data2", "data3"]);
+        assert_eq!(ConsumableVec::from(vec!["data2", "data3"]), at);
+        assert_eq!(1, at.evicted());
+    }
+
+    #[test]
+    fn equal_vecs_should_hash_equal() {
+        let mut set = HashSet::new();
+        set.insert(ConsumableVec::from(vec!["data".to_string()]));
+        let mut at = ConsumableVec::default();
+        at.add("data".to_string());
+        assert!(set.contains(&at));
+        at.clear();
+        assert_eq!(ConsumableVec::<String>::default(), at);
+    }
+}
+
+#[cfg(test)]
+mod test_structured_replies {
+    use super::*;
+    use len_trait::Len;
+
+    #[derive(Debug, PartialEq)]
+    enum Reply {
+        Ok,
+        Error(u16),
+        Data(String),
+    }
+
+    #[test]
+    fn consume_where_when_predicate_never_true_should_return_none() {
+        let mut at = ConsumableVec::new(Some(vec![Reply::Ok, Reply::Error(3)]));
+        assert!(at.consume_where(|r| matches!(r, Reply::Data(_))).is_none());
+        assert_eq!(2, at.len());
+    }
+
+    #[test]
+    fn consume_where_should_move_matches_in_insertion_order() {
+        let mut at = ConsumableVec::new(Some(vec![
+            Reply::Data("first".to_string()),
+            Reply::Ok,
+            Reply::Data("second".to_string()),
+        ]));
+        let consumed = at.consume_where(|r| matches!(r, Reply::Data(_))).unwrap();
+        assert_eq!(
+            &vec![
+                Reply::Data("first".to_string()),
+                Reply::Data("second".to_string())
+            ],
+            consumed.inner()
+        );
+        assert_eq!(&vec![Reply::Ok], at.inner());
+    }
+
+    #[test]
+    fn shared_consume_where_should_remove_values_from_data() {
+        let at = SharedConsumableVec::new(None);
+        let producer = at.clone();
+        producer.add(Reply::Error(3));
+        producer.add(Reply::Ok);
+        producer.add(Reply::Error(10));
+        let consumed = at.consume_where(|r| matches!(r, Reply::Error(_))).unwrap();
+        assert_eq!(2, consumed.len());
+        assert_eq!(1, at.len());
+    }
+}
+
+#[cfg(test)]
+mod test_shared_at_replies {
+
+    use super::*;
+    use len_trait::Len;
+
+    #[test]
+    fn consume_when_pattern_not_in_replies_should_return_none() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        assert!(at.consume("pattern".to_string()).is_none());
+    }
+
+    #[test]
+    fn consume_when_pattern_in_replies_should_return_some() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        assert!(at.consume("da".to_string()).is_some());
+    }
+
+    #[test]
+    fn consume_when_pattern_in_replies_should_have_data() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        let consumed = at.consume("da".to_string()).unwrap();
+        assert_eq!(1, consumed.len());
+        assert_eq!("data".to_string(), consumed.data[0]);
+    }
+
+    #[test]
+    fn consume_when_pattern_in_replies_multiple_times_should_have_data_multipletimes() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        at.add("data2".to_string());
+        let consumed = at.consume("da".to_string()).unwrap();
         assert_eq!(2, consumed.len());
         assert_eq!("data2".to_string(), consumed.data[1]);
     }
@@ -346,4 +1859,158 @@ mod test_shared_at_replies {
         let _ = at.consume("da".to_string()).unwrap();
         assert_eq!(1, at.len());
     }
+
+    #[test]
+    fn consume_blocking_when_pattern_in_replies_should_return_immediately() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        let consumed = at
+            .consume_blocking("da".to_string(), Duration::from_secs(0))
+            .unwrap();
+        assert_eq!(1, consumed.len());
+    }
+
+    #[test]
+    fn consume_blocking_when_pattern_not_added_should_time_out() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        let consumed = at.consume_blocking("pattern".to_string(), Duration::from_millis(50));
+        assert!(consumed.is_none());
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn consume_blocking_should_wake_up_when_pattern_gets_added() {
+        let at = SharedConsumableVec::default();
+        let producer = at.clone();
+
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            producer.add("ata".to_string());
+            producer.add("data".to_string());
+        });
+
+        let consumed = at
+            .consume_blocking("da".to_string(), Duration::from_secs(10))
+            .unwrap();
+        handle.join().unwrap();
+        assert_eq!("data".to_string(), consumed.data[0]);
+    }
+
+    #[test]
+    fn consume_matching_when_pattern_not_in_replies_should_return_none() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        let pattern = StringPattern::new("da").mode(MatchMode::Exact);
+        assert!(at.consume_matching(&pattern).is_none());
+        assert_eq!(1, at.len());
+    }
+
+    fn poison(at: &SharedConsumableVec<String>) {
+        let poisoner = at.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poisoning shared data");
+        })
+        .join();
+    }
+
+    #[test]
+    fn try_methods_when_poisoned_should_return_error() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        poison(&at);
+        assert_eq!(Err(ConsumeError::Poisoned), at.try_len());
+        assert_eq!(
+            Err(TryAddError::Poisoned("data2".to_string())),
+            at.try_add("data2".to_string())
+        );
+        assert!(at.try_consume("da".to_string()).is_err());
+    }
+
+    #[test]
+    fn try_methods_when_poisoned_and_recovering_should_use_data() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        poison(&at);
+        at.set_poison_policy(PoisonPolicy::Recover);
+        assert_eq!(Ok(()), at.try_add("data2".to_string()));
+        assert_eq!(2, at.len());
+        let consumed = at.try_consume("da".to_string()).unwrap().unwrap();
+        assert_eq!(2, consumed.len());
+    }
+
+    #[test]
+    fn try_add_when_capacity_limit_reached_should_hand_back_item() {
+        let at = SharedConsumableVec::with_capacity_limit(2);
+        assert_eq!(Ok(()), at.try_add("data1".to_string()));
+        assert_eq!(Ok(()), at.try_add("data2".to_string()));
+        assert_eq!(
+            Err(TryAddError::Full("data3".to_string())),
+            at.try_add("data3".to_string())
+        );
+        let _ = at.consume_first("data1".to_string()).unwrap();
+        assert_eq!(Ok(()), at.try_add("data3".to_string()));
+    }
+
+    #[test]
+    fn add_when_capacity_limit_reached_should_block_until_consumed() {
+        let at = SharedConsumableVec::with_capacity_limit(1);
+        at.add("data1".to_string());
+        let producer = at.clone();
+
+        let handle = std::thread::spawn(move || producer.add("data2".to_string()));
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(
+            vec!["data1".to_string()],
+            at.consume("da".to_string()).unwrap().data
+        );
+        handle.join().unwrap();
+        assert_eq!(1, at.len());
+    }
+
+    #[test]
+    fn add_when_capacity_limit_reached_should_block_until_expired() {
+        let at = SharedConsumableVec::with_capacity_limit(1);
+        at.add_with_ttl("data1".to_string(), Duration::from_millis(50));
+
+        let start = Instant::now();
+        at.add("data2".to_string());
+        assert!(start.elapsed() < Duration::from_millis(500));
+        assert_eq!(
+            vec!["data2".to_string()],
+            at.consume("da".to_string()).unwrap().data
+        );
+    }
+
+    #[test]
+    fn peek_where_should_not_remove_values_from_data() {
+        let at = SharedConsumableVec::default();
+        at.add("data".to_string());
+        at.add("ata".to_string());
+        let peeked = at.peek_where(|d| d.len() == 3).unwrap();
+        assert_eq!(vec!["ata".to_string()], peeked.data);
+        assert!(at.contains_where(|d| d == "data"));
+        assert!(at.contains("da".to_string()));
+        assert!(at.peek("pattern".to_string()).is_none());
+        assert_eq!(2, at.len());
+    }
+
+    #[test]
+    fn consume_first_should_hand_one_match_to_each_consumer() {
+        let at = SharedConsumableVec::default();
+        let other = at.clone();
+        at.add("data1".to_string());
+        at.add("data2".to_string());
+        assert_eq!(
+            Some("data1".to_string()),
+            at.consume_first("da".to_string())
+        );
+        assert_eq!(
+            Some("data2".to_string()),
+            other.consume_first("da".to_string())
+        );
+        assert!(at.consume_first("da".to_string()).is_none());
+    }
 }
@@ -0,0 +1,250 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Prometheus text exposition of `Stats`
+
+use crate::{SharedConsumableVec, Stats};
+use std::fmt;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+type Metric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Stats) -> String,
+);
+
+/// Returns the current statistics of a registered vector, `None` once it got dropped
+type StatsSource = Box<dyn Fn() -> Option<Stats> + Send>;
+
+const METRICS: [Metric; 7] = [
+    (
+        "consumable_vec_added_total",
+        "counter",
+        "Entries added to the vector.",
+        |stats| stats.added.to_string(),
+    ),
+    (
+        "consumable_vec_consumed_total",
+        "counter",
+        "Entries consumed from the vector.",
+        |stats| stats.consumed.to_string(),
+    ),
+    (
+        "consumable_vec_cleared_total",
+        "counter",
+        "Entries removed by clear.",
+        |stats| stats.cleared.to_string(),
+    ),
+    (
+        "consumable_vec_empty_consumes_total",
+        "counter",
+        "Consume calls which found no matching entry.",
+        |stats| stats.empty_consumes.to_string(),
+    ),
+    (
+        "consumable_vec_len",
+        "gauge",
+        "Current number of entries.",
+        |stats| stats.len.to_string(),
+    ),
+    (
+        "consumable_vec_high_water_mark",
+        "gauge",
+        "Highest number of entries held at the same time.",
+        |stats| stats.high_water_mark.to_string(),
+    ),
+    (
+        "consumable_vec_lock_hold_seconds_average",
+        "gauge",
+        "Average time the inner data stayed locked per access.",
+        |stats| stats.average_lock_hold_time.as_secs_f64().to_string(),
+    ),
+];
+
+impl Stats {
+    /// Renders the counters in the Prometheus text format, labeled with `queue`
+    ///
+    /// Example:
+    /// ```
+    /// use consumable_vec::SharedConsumableVec;
+    ///
+    /// let at = SharedConsumableVec::default();
+    /// at.add("OK".to_string());
+    ///
+    /// let text = at.stats().to_prometheus("at");
+    /// assert!(text.contains("consumable_vec_added_total{queue=\"at\"} 1\n"));
+    /// ```
+    pub fn to_prometheus(&self, queue: &str) -> String {
+        render(&[(queue.to_string(), *self)])
+    }
+}
+
+/// Named `SharedConsumableVec`s whose statistics are exported together
+///
+/// Clones share the registered vectors. The registry does not keep the
+/// vectors alive, dropped ones are no longer rendered.
+#[derive(Clone, Default)]
+pub struct MetricsRegistry {
+    queues: Arc<Mutex<Vec<(String, StatsSource)>>>,
+}
+
+impl MetricsRegistry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        MetricsRegistry::default()
+    }
+
+    /// Registers `vec` under the queue name `name`, replacing any vector
+    /// registered under the same name before
+    pub fn register<S, T>(&self, name: S, vec: &SharedConsumableVec<T>)
+    where
+        S: Into<String>,
+        T: Send + 'static,
+    {
+        let name = name.into();
+        let shared = Arc::downgrade(&vec.shared);
+        let stats = move || {
+            shared
+                .upgrade()
+                .map(|shared| SharedConsumableVec { shared }.stats())
+        };
+
+        let mut queues = self.queues.lock().unwrap();
+        queues.retain(|(registered, _)| *registered != name);
+        queues.push((name, Box::new(stats)));
+    }
+
+    /// Removes the vector registered under `name` and returns whether it existed
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut queues = self.queues.lock().unwrap();
+        let len = queues.len();
+        queues.retain(|(registered, _)| registered != name);
+        queues.len() != len
+    }
+
+    /// Renders the statistics of all registered vectors in the Prometheus
+    /// text format, each labeled with its queue name
+    pub fn render(&self) -> String {
+        let stats: Vec<(String, Stats)> = self
+            .queues
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(name, stats)| stats().map(|stats| (name.clone(), stats)))
+            .collect();
+        render(&stats)
+    }
+}
+
+impl fmt::Debug for MetricsRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queues = self.queues.lock().unwrap();
+        f.debug_list()
+            .entries(queues.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+fn render(stats: &[(String, Stats)]) -> String {
+    let mut text = String::new();
+    for (name, kind, help, value) in METRICS.iter() {
+        writeln!(text, "# HELP {} {}", name, help).unwrap();
+        writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+        for (queue, stats) in stats {
+            writeln!(
+                text,
+                "{}{{queue=\"{}\"}} {}",
+                name,
+                escape(queue),
+                value(stats)
+            )
+            .unwrap();
+        }
+    }
+    text
+}
+
+/// Escapes a label value as required by the text format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test_metrics {
+    use super::*;
+    use crate::Consumable;
+
+    #[test]
+    fn to_prometheus_should_render_all_metrics_labeled_with_queue() {
+        let at = SharedConsumableVec::default();
+        at.add("data1".to_string());
+        at.add("data2".to_string());
+        let _ = at.consume("data1".to_string());
+
+        let text = at.stats().to_prometheus("at");
+        assert!(text.contains(
+            "# HELP consumable_vec_added_total Entries added to the vector.\n\
+             # TYPE consumable_vec_added_total counter\n\
+             consumable_vec_added_total{queue=\"at\"} 2\n"
+        ));
+        assert!(text.contains("consumable_vec_consumed_total{queue=\"at\"} 1\n"));
+        assert!(text.contains("# TYPE consumable_vec_len gauge\n"));
+        assert!(text.contains("consumable_vec_len{queue=\"at\"} 1\n"));
+        assert!(text.contains("consumable_vec_high_water_mark{queue=\"at\"} 2\n"));
+        assert_eq!(7 * 3, text.lines().count());
+    }
+
+    #[test]
+    fn to_prometheus_should_escape_queue_label() {
+        let text = Stats::default().to_prometheus("a\"b\\c\nd");
+        assert!(text.contains("consumable_vec_len{queue=\"a\\\"b\\\\c\\nd\"} 0\n"));
+    }
+
+    #[test]
+    fn render_should_group_samples_of_all_registered_vectors() {
+        let registry = MetricsRegistry::new();
+        let at = SharedConsumableVec::default();
+        let urc = SharedConsumableVec::default();
+        at.add("OK".to_string());
+        urc.add("RING".to_string());
+        urc.add("RING".to_string());
+        registry.register("at", &at);
+        registry.register("urc", &urc);
+
+        let text = registry.render();
+        assert!(text.contains(
+            "# TYPE consumable_vec_len gauge\n\
+             consumable_vec_len{queue=\"at\"} 1\n\
+             consumable_vec_len{queue=\"urc\"} 2\n"
+        ));
+    }
+
+    #[test]
+    fn render_when_vector_dropped_or_unregistered_should_skip_it() {
+        let registry = MetricsRegistry::new();
+        let at = SharedConsumableVec::<String>::default();
+        let urc = SharedConsumableVec::<String>::default();
+        registry.register("at", &at);
+        registry.register("urc", &urc);
+
+        drop(at);
+        assert!(registry.unregister("urc"));
+        assert!(!registry.unregister("urc"));
+
+        let text = registry.render();
+        assert!(!text.contains("queue="));
+        assert_eq!("[\"at\"]", format!("{:?}", registry));
+    }
+}
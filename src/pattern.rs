@@ -0,0 +1,191 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Configurable matching of `String` and byte buffer entries
+
+/// Where in an entry the pattern needs to be found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// Entry starts with the pattern
+    #[default]
+    Prefix,
+    /// Entry ends with the pattern
+    Suffix,
+    /// Entry contains the pattern anywhere
+    Contains,
+    /// Entry equals the pattern
+    Exact,
+}
+
+/// Search pattern for consuming `String` entries
+///
+/// A pattern created by `new` behaves like the pattern passed to `consume_mut`:
+/// prefix matching, case sensitive, with pattern and entries trimmed.
+///
+/// Example:
+/// ```
+/// use consumable_vec::{MatchMode, StringPattern};
+///
+/// let pattern = StringPattern::new("ok")
+///     .mode(MatchMode::Exact)
+///     .case_insensitive(true);
+///
+/// assert!(pattern.matches(" OK\r\n"));
+/// assert!(!pattern.matches("OKAY"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringPattern {
+    pattern: String,
+    mode: MatchMode,
+    case_insensitive: bool,
+    trim: bool,
+}
+
+impl StringPattern {
+    pub fn new<S: Into<String>>(pattern: S) -> Self {
+        StringPattern {
+            pattern: pattern.into(),
+            mode: MatchMode::default(),
+            case_insensitive: false,
+            trim: true,
+        }
+    }
+
+    /// Sets where the pattern needs to be found, defaults to `MatchMode::Prefix`
+    pub fn mode(mut self, mode: MatchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Ignore case when matching, defaults to `false`
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Trim whitespace of pattern and entries before matching, defaults to `true`
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Returns `true` if `value` is matched by this pattern
+    pub fn matches(&self, value: &str) -> bool {
+        let (pattern, value) = if self.trim {
+            (self.pattern.trim(), value.trim())
+        } else {
+            (self.pattern.as_str(), value)
+        };
+
+        if self.case_insensitive {
+            self.matches_mode(&pattern.to_lowercase(), &value.to_lowercase())
+        } else {
+            self.matches_mode(pattern, value)
+        }
+    }
+
+    fn matches_mode(&self, pattern: &str, value: &str) -> bool {
+        match self.mode {
+            MatchMode::Prefix => value.starts_with(pattern),
+            MatchMode::Suffix => value.ends_with(pattern),
+            MatchMode::Contains => value.contains(pattern),
+            MatchMode::Exact => value == pattern,
+        }
+    }
+}
+
+/// Search pattern for consuming byte buffer entries
+///
+/// Entries are matched by prefix. A pattern created by `new` ignores leading and
+/// trailing ASCII whitespace of pattern and entries, like `StringPattern`.
+///
+/// Example:
+/// ```
+/// use consumable_vec::BytePattern;
+///
+/// let pattern = BytePattern::new(b"\x02OK".to_vec());
+/// assert!(pattern.matches(b"\r\n\x02OK\xff"));
+/// assert!(!pattern.trim(false).matches(b"\r\n\x02OK\xff"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BytePattern {
+    pattern: Vec<u8>,
+    trim: bool,
+}
+
+impl BytePattern {
+    pub fn new<B: Into<Vec<u8>>>(pattern: B) -> Self {
+        BytePattern {
+            pattern: pattern.into(),
+            trim: true,
+        }
+    }
+
+    /// Ignore ASCII whitespace around pattern and entries, defaults to `true`
+    pub fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    /// Returns `true` if `value` starts with this pattern
+    pub fn matches(&self, value: &[u8]) -> bool {
+        if self.trim {
+            value.trim_ascii().starts_with(self.pattern.trim_ascii())
+        } else {
+            value.starts_with(&self.pattern)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_string_pattern {
+    use super::*;
+
+    #[test]
+    fn new_pattern_should_match_trimmed_prefix() {
+        let pattern = StringPattern::new(" +CREG");
+        assert!(pattern.matches("\t+CREG: 1\r\n"));
+        assert!(!pattern.matches("+creg: 1"));
+    }
+
+    #[test]
+    fn suffix_should_match_end_of_entry() {
+        let pattern = StringPattern::new("OK").mode(MatchMode::Suffix);
+        assert!(pattern.matches("ALL OK\r\n"));
+        assert!(!pattern.matches("OK then"));
+    }
+
+    #[test]
+    fn contains_should_match_anywhere() {
+        let pattern = StringPattern::new("ERROR").mode(MatchMode::Contains);
+        assert!(pattern.matches("+CME ERROR: 10"));
+        assert!(!pattern.matches("OK"));
+    }
+
+    #[test]
+    fn exact_without_trim_should_respect_whitespace() {
+        let pattern = StringPattern::new("OK").mode(MatchMode::Exact).trim(false);
+        assert!(pattern.matches("OK"));
+        assert!(!pattern.matches("OK\r\n"));
+    }
+
+    #[test]
+    fn case_insensitive_should_ignore_case() {
+        let pattern = StringPattern::new("ring").case_insensitive(true);
+        assert!(pattern.matches("RING"));
+    }
+
+    #[test]
+    fn byte_pattern_should_match_prefix_of_non_utf8_entries() {
+        let pattern = BytePattern::new(vec![0xfe, 0x01]);
+        assert!(pattern.matches(&[b' ', 0xfe, 0x01, 0xff]));
+        assert!(!pattern.matches(&[0x01, 0xfe]));
+    }
+}
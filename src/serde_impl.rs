@@ -0,0 +1,77 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Serde support, enabled by the `serde` feature
+//!
+//! Both vectors are represented by the sequence of their entries, like a `Vec<T>`.
+//! The configuration of a vector, e.g. its time to live, is not serialized.
+//! Neither are entries whose time to live elapsed.
+
+use crate::{ConsumableVec, SharedConsumableVec};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+impl<T: Serialize> Serialize for ConsumableVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.unexpired())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for ConsumableVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(ConsumableVec::from)
+    }
+}
+
+/// Serializes a snapshot of the entries at the time of locking
+impl<T: Serialize> Serialize for SharedConsumableVec<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let data = self.lock().map_err(serde::ser::Error::custom)?;
+        data.serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SharedConsumableVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(|data| SharedConsumableVec::new(Some(data)))
+    }
+}
+
+#[cfg(test)]
+mod test_serde {
+    use crate::{ConsumableVec, SharedConsumableVec};
+    use len_trait::Len;
+    use std::time::Duration;
+
+    #[test]
+    fn consumable_vec_should_round_trip_as_sequence() {
+        let at = ConsumableVec::from(vec!["data".to_string(), "ata".to_string()]);
+        let json = serde_json::to_string(&at).unwrap();
+        assert_eq!(r#"["data","ata"]"#, json);
+        let restored: ConsumableVec<String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(at, restored);
+    }
+
+    #[test]
+    fn consumable_vec_should_not_serialize_expired_values() {
+        let mut at = ConsumableVec::default();
+        at.add_with_ttl("data".to_string(), Duration::from_millis(1));
+        at.add("ata".to_string());
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(r#"["ata"]"#, serde_json::to_string(&at).unwrap());
+    }
+
+    #[test]
+    fn shared_vec_should_serialize_snapshot_and_restore_fixture() {
+        let at: SharedConsumableVec<String> = serde_json::from_str(r#"["data","ata"]"#).unwrap();
+        assert_eq!(2, at.len());
+        let _ = at.consume_first("da".to_string());
+        assert_eq!(r#"["ata"]"#, serde_json::to_string(&at).unwrap());
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Usage statistics of a `SharedConsumableVec`
+
+use crate::ConsumableVec;
+use std::convert::TryFrom;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, LockResult, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Snapshot of the counters of a `SharedConsumableVec`, returned by `stats`
+///
+/// Entries handed to a subscription count as added and consumed. Entries
+/// dropped because their time to live elapsed or evicted in ring mode are
+/// not counted as consumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Stats {
+    /// Entries added in total
+    pub added: u64,
+    /// Entries consumed in total
+    pub consumed: u64,
+    /// Entries removed by `clear` in total
+    pub cleared: u64,
+    /// Current number of entries
+    pub len: usize,
+    /// Highest number of entries held at the same time
+    pub high_water_mark: usize,
+    /// Consume calls which returned `None`
+    pub empty_consumes: u64,
+    /// Average time the inner data stayed locked per access
+    pub average_lock_hold_time: Duration,
+}
+
+/// Counters shared between all clones of a `SharedConsumableVec`
+///
+/// Most counters are only changed while the data is locked, the atomics
+/// merely allow reading them without the lock.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    added: AtomicU64,
+    consumed: AtomicU64,
+    cleared: AtomicU64,
+    high_water_mark: AtomicUsize,
+    empty_consumes: AtomicU64,
+    lock_holds: AtomicU64,
+    lock_hold_nanos: AtomicU64,
+}
+
+impl Counters {
+    /// Records an entry added to the data, which now holds `len` entries
+    pub(crate) fn record_add(&self, len: usize) {
+        self.added.fetch_add(1, Ordering::Relaxed);
+        self.record_len(len);
+    }
+
+    /// Records an entry handed to a subscription instead of being stored
+    pub(crate) fn record_routed(&self) {
+        self.added.fetch_add(1, Ordering::Relaxed);
+        self.consumed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_len(&self, len: usize) {
+        self.high_water_mark.fetch_max(len, Ordering::Relaxed);
+    }
+
+    /// Records a consume call removing `consumed` entries, zero for a call returning `None`
+    pub(crate) fn record_consume(&self, consumed: usize) {
+        if consumed > 0 {
+            self.consumed.fetch_add(consumed as u64, Ordering::Relaxed);
+        } else {
+            self.empty_consumes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_clear(&self, cleared: usize) {
+        self.cleared.fetch_add(cleared as u64, Ordering::Relaxed);
+    }
+
+    fn record_lock_hold(&self, held: Duration) {
+        let nanos = u64::try_from(held.as_nanos()).unwrap_or(u64::MAX);
+        self.lock_holds.fetch_add(1, Ordering::Relaxed);
+        self.lock_hold_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self, len: usize) -> Stats {
+        let holds = self.lock_holds.load(Ordering::Relaxed);
+        let nanos = self.lock_hold_nanos.load(Ordering::Relaxed);
+
+        Stats {
+            added: self.added.load(Ordering::Relaxed),
+            consumed: self.consumed.load(Ordering::Relaxed),
+            cleared: self.cleared.load(Ordering::Relaxed),
+            len,
+            high_water_mark: self.high_water_mark.load(Ordering::Relaxed),
+            empty_consumes: self.empty_consumes.load(Ordering::Relaxed),
+            average_lock_hold_time: Duration::from_nanos(nanos.checked_div(holds).unwrap_or(0)),
+        }
+    }
+}
+
+/// Locked inner data of a `SharedConsumableVec`, recording the hold time when dropped
+pub(crate) struct DataGuard<'a, T> {
+    // only `None` while waiting on a condition variable
+    guard: Option<MutexGuard<'a, ConsumableVec<T>>>,
+    counters: &'a Counters,
+    locked_at: Instant,
+}
+
+impl<'a, T> DataGuard<'a, T> {
+    pub(crate) fn new(
+        result: LockResult<MutexGuard<'a, ConsumableVec<T>>>,
+        counters: &'a Counters,
+    ) -> LockResult<Self> {
+        let wrap = |guard| DataGuard {
+            guard: Some(guard),
+            counters,
+            locked_at: Instant::now(),
+        };
+
+        result
+            .map(wrap)
+            .map_err(|e| PoisonError::new(wrap(e.into_inner())))
+    }
+
+    /// Releases the lock until `condvar` gets notified or `timeout` elapsed
+    ///
+    /// Without `timeout`, this waits until `condvar` gets notified.
+    pub(crate) fn wait(mut self, condvar: &Condvar, timeout: Option<Duration>) -> LockResult<Self> {
+        let guard = self.guard.take().expect("data guard is locked");
+        self.counters.record_lock_hold(self.locked_at.elapsed());
+
+        let result = match timeout {
+            Some(timeout) => match condvar.wait_timeout(guard, timeout) {
+                Ok((guard, _)) => Ok(guard),
+                Err(e) => Err(PoisonError::new(e.into_inner().0)),
+            },
+            None => condvar.wait(guard),
+        };
+
+        DataGuard::new(result, self.counters)
+    }
+}
+
+impl<T> Deref for DataGuard<'_, T> {
+    type Target = ConsumableVec<T>;
+
+    fn deref(&self) -> &ConsumableVec<T> {
+        self.guard.as_ref().expect("data guard is locked")
+    }
+}
+
+impl<T> DerefMut for DataGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut ConsumableVec<T> {
+        self.guard.as_mut().expect("data guard is locked")
+    }
+}
+
+impl<T> Drop for DataGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.guard.is_some() {
+            self.counters.record_lock_hold(self.locked_at.elapsed());
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Standing subscriptions receiving matching entries directly when added
+
+use std::fmt;
+
+/// Identifies a subscription registered on a `SharedConsumableVec`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Predicate<T> = Box<dyn Fn(&T) -> bool + Send>;
+type Sink<T> = Box<dyn FnMut(T) + Send>;
+
+struct Subscription<T> {
+    id: SubscriptionId,
+    predicate: Predicate<T>,
+    sink: Sink<T>,
+}
+
+/// All subscriptions of a `SharedConsumableVec`, in order of registration
+pub(crate) struct Subscriptions<T> {
+    next_id: u64,
+    list: Vec<Subscription<T>>,
+}
+
+impl<T> Subscriptions<T> {
+    pub(crate) fn new() -> Self {
+        Subscriptions {
+            next_id: 0,
+            list: Vec::new(),
+        }
+    }
+
+    pub(crate) fn subscribe(&mut self, predicate: Predicate<T>, sink: Sink<T>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.list.push(Subscription {
+            id,
+            predicate,
+            sink,
+        });
+        id
+    }
+
+    pub(crate) fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count = self.list.len();
+        self.list.retain(|s| s.id != id);
+        self.list.len() != count
+    }
+
+    /// Hands `item` to the oldest subscription matching it
+    ///
+    /// Returns `item` back if no subscription matched.
+    pub(crate) fn route(&mut self, item: T) -> Option<T> {
+        match self.list.iter_mut().find(|s| (s.predicate)(&item)) {
+            Some(subscription) => {
+                (subscription.sink)(item);
+                None
+            }
+            None => Some(item),
+        }
+    }
+}
+
+// not derived, predicates and sinks can not be printed
+impl<T> fmt::Debug for Subscriptions<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.list.iter().map(|s| s.id))
+            .finish()
+    }
+}
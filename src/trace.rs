@@ -0,0 +1,130 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Instrumentation with `tracing`, expanding to nothing without the `tracing` feature
+
+/// Enters a debug span until the end of the enclosing block
+macro_rules! span {
+    ($($args:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($($args)+).entered();
+    };
+}
+
+/// Emits a debug event
+macro_rules! event {
+    ($($args:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($args)+);
+    };
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test_tracing {
+    use crate::{Consumable, ConsumableMut, ConsumableVec, SharedConsumableVec};
+    use std::fmt;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records spans and events as `name field=value ...` lines
+    #[derive(Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        next_id: AtomicU64,
+    }
+
+    struct Line(String);
+
+    impl Visit for Line {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0 += &format!(" {}={:?}", field.name(), value);
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut line = Line(span.metadata().name().to_string());
+            span.record(&mut line);
+            self.lines.lock().unwrap().push(line.0);
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut line = Line("event".to_string());
+            event.record(&mut line);
+            self.lines.lock().unwrap().push(line.0);
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn record<F: FnOnce()>(f: F) -> Vec<String> {
+        let recorder = Recorder::default();
+        let lines = recorder.lines.clone();
+        tracing::subscriber::with_default(recorder, f);
+        let lines = lines.lock().unwrap().clone();
+        lines
+    }
+
+    #[test]
+    fn consume_should_trace_pattern_match_count_and_remaining_len() {
+        let lines = record(|| {
+            let at = SharedConsumableVec::default();
+            at.add("data1".to_string());
+            at.add("ata".to_string());
+            let _ = at.consume("da".to_string());
+            at.clear();
+        });
+
+        assert_eq!(
+            vec![
+                "add",
+                "event message=added len=1",
+                "add",
+                "event message=added len=2",
+                "consume pattern=da",
+                "consume_mut pattern=da",
+                "event message=consumed matched=1 remaining=1",
+                "clear",
+                "event message=cleared removed=1",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn consume_mut_should_trace_pattern() {
+        let lines = record(|| {
+            let mut at = ConsumableVec::new(Some(vec![b"OK".to_vec()]));
+            assert!(at.consume_mut(b"ERROR".to_vec()).is_none());
+        });
+
+        assert_eq!(
+            vec![
+                "consume_mut pattern=ERROR",
+                "event message=consumed matched=0 remaining=1",
+            ],
+            lines
+        );
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright (c) Siemens AG, 2021
+//
+// Authors:
+//  Dominik Tacke <dominik.tacke@siemens.com>
+//
+// This work is licensed under the terms of the MIT.  See
+// the LICENSE-MIT file in the top-level directory.
+//
+// SPDX-License-Identifier: MIT
+
+//! Feeding a `SharedConsumableVec<String>` from a byte stream
+
+use crate::SharedConsumableVec;
+use std::io;
+
+/// Terminators a `LineSplitter` splits the byte stream at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineTerminator {
+    /// `\r\n`
+    CrLf,
+    /// `\n`
+    Lf,
+    /// `\r`
+    Cr,
+    /// Any of `\r\n`, `\n` and `\r`
+    #[default]
+    Any,
+}
+
+/// Writer splitting the written bytes into lines, which are added to a
+/// `SharedConsumableVec<String>`
+///
+/// Incomplete lines are buffered until their terminator got written. Line
+/// terminators are not part of the added lines, invalid UTF-8 is replaced by
+/// `U+FFFD`. Empty lines are skipped unless configured otherwise.
+///
+/// Writing blocks while a capacity limited vector is full. If the vector is
+/// poisoned, a write stops at the terminator of the line which could not be
+/// added. It reports the bytes before as written, or fails with an `io::Error`
+/// if there are none, and the line stays buffered.
+///
+/// Example:
+/// ```
+/// use consumable_vec::{Consumable, LineSplitter, SharedConsumableVec};
+/// use std::io::Write;
+///
+/// let replies = SharedConsumableVec::default();
+/// let mut serial = LineSplitter::new(replies.clone());
+///
+/// serial.write_all(b"\r\n+CSQ: 20,99\r\n\r\nO").unwrap();
+/// serial.write_all(b"K\r\n").unwrap();
+///
+/// assert!(replies.consume("+CSQ".to_string()).is_some());
+/// assert!(replies.consume("OK".to_string()).is_some());
+/// ```
+#[derive(Debug)]
+pub struct LineSplitter {
+    vec: SharedConsumableVec<String>,
+    terminator: LineTerminator,
+    skip_empty: bool,
+    buffer: Vec<u8>,
+    /// a `\r` was just treated as terminator, so a following `\n` belongs to it
+    skip_lf: bool,
+}
+
+impl LineSplitter {
+    pub fn new(vec: SharedConsumableVec<String>) -> Self {
+        LineSplitter {
+            vec,
+            terminator: LineTerminator::default(),
+            skip_empty: true,
+            buffer: Vec::new(),
+            skip_lf: false,
+        }
+    }
+
+    /// Sets the terminator to split at, defaults to `LineTerminator::Any`
+    pub fn terminator(mut self, terminator: LineTerminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Skip lines without any content, defaults to `true`
+    pub fn skip_empty(mut self, skip_empty: bool) -> Self {
+        self.skip_empty = skip_empty;
+        self
+    }
+
+    /// Adds a buffered incomplete line, e.g. when the byte stream ended
+    pub fn finish(mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.add_line()?;
+        }
+        Ok(())
+    }
+
+    /// Handles a single byte, leaving the state as before if its line could not be added
+    fn push(&mut self, byte: u8) -> io::Result<()> {
+        match self.terminator {
+            LineTerminator::CrLf => {
+                self.buffer.push(byte);
+                if self.buffer.ends_with(b"\r\n") {
+                    self.buffer.truncate(self.buffer.len() - 2);
+                    if let Err(e) = self.add_line() {
+                        self.buffer.push(b'\r');
+                        return Err(e);
+                    }
+                }
+            }
+            LineTerminator::Lf if byte == b'\n' => self.add_line()?,
+            LineTerminator::Cr if byte == b'\r' => self.add_line()?,
+            LineTerminator::Any => {
+                let skip_lf = std::mem::replace(&mut self.skip_lf, false);
+                match byte {
+                    b'\n' if skip_lf => {}
+                    b'\n' => self.add_line()?,
+                    b'\r' => {
+                        if let Err(e) = self.add_line() {
+                            self.skip_lf = skip_lf;
+                            return Err(e);
+                        }
+                        self.skip_lf = true;
+                    }
+                    _ => self.buffer.push(byte),
+                }
+            }
+            _ => self.buffer.push(byte),
+        }
+        Ok(())
+    }
+
+    /// Adds the buffered line, which stays buffered if it could not be added
+    fn add_line(&mut self) -> io::Result<()> {
+        if !self.skip_empty || !self.buffer.is_empty() {
+            let line = String::from_utf8_lossy(&self.buffer).into_owned();
+            self.vec
+                .try_insert(line, None)
+                .map_err(|(e, _)| io::Error::other(e))?;
+        }
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl io::Write for LineSplitter {
+    /// Blocks while a capacity limited vector is full
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (written, &byte) in buf.iter().enumerate() {
+            if let Err(e) = self.push(byte) {
+                // the next write starts at the failing byte and reports the error
+                return if written > 0 { Ok(written) } else { Err(e) };
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Incomplete lines stay buffered, see `finish`
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_line_splitter {
+    use super::*;
+    use std::io::Write;
+
+    fn split(mut splitter: LineSplitter, chunks: &[&[u8]]) -> Vec<String> {
+        let vec = splitter.vec.clone();
+        for chunk in chunks {
+            splitter.write_all(chunk).unwrap();
+        }
+        vec.consume_where(|_| true)
+            .map(|c| c.inner().clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn any_terminator_should_treat_split_crlf_as_one_terminator() {
+        let splitter = LineSplitter::new(SharedConsumableVec::default());
+        let lines = split(splitter, &[b"OK\r", b"\nRING\rdata\n"]);
+        assert_eq!(vec!["OK", "RING", "data"], lines);
+    }
+
+    #[test]
+    fn crlf_terminator_should_keep_single_cr_and_lf() {
+        let splitter =
+            LineSplitter::new(SharedConsumableVec::default()).terminator(LineTerminator::CrLf);
+        let lines = split(splitter, &[b"a\rb\nc\r", b"\n"]);
+        assert_eq!(vec!["a\rb\nc"], lines);
+    }
+
+    #[test]
+    fn incomplete_line_should_only_be_added_on_finish() {
+        let vec = SharedConsumableVec::default();
+        let mut splitter = LineSplitter::new(vec.clone()).terminator(LineTerminator::Lf);
+        splitter.write_all(b"\n\nOK\nda").unwrap();
+        assert_eq!(1, len_trait::Len::len(&vec));
+        splitter.finish().unwrap();
+        assert_eq!(Some("da".to_string()), vec.consume_first("da".to_string()));
+    }
+
+    #[test]
+    fn empty_lines_should_be_added_when_not_skipped() {
+        let splitter = LineSplitter::new(SharedConsumableVec::default())
+            .terminator(LineTerminator::Cr)
+            .skip_empty(false);
+        let lines = split(splitter, &[b"\r\xffOK\r"]);
+        assert_eq!(vec!["", "\u{fffd}OK"], lines);
+    }
+
+    #[test]
+    fn write_when_vec_poisoned_should_stop_at_failing_line() {
+        let vec = SharedConsumableVec::default();
+        let poisoner = vec.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poisoning shared data");
+        })
+        .join();
+
+        let mut splitter = LineSplitter::new(vec.clone());
+        assert_eq!(2, splitter.write(b"OK\r\n").unwrap());
+        let err = splitter.write(b"\r\n").unwrap_err();
+        assert_eq!(io::ErrorKind::Other, err.kind());
+        assert_eq!(b"OK".to_vec(), splitter.buffer);
+
+        let mut splitter = LineSplitter::new(vec).terminator(LineTerminator::CrLf);
+        assert_eq!(3, splitter.write(b"OK\r\n").unwrap());
+        assert!(splitter.write(b"\n").is_err());
+        assert_eq!(b"OK\r".to_vec(), splitter.buffer);
+    }
+}